@@ -0,0 +1,135 @@
+use std::env;
+
+/// Options of the command line, with their short names, that can also be given as
+/// inputs of the action (see `action.yml`), with `_` in place of `-`.
+const OPTIONS: &[(&str, &str)] = &[
+    ("rules", "r"),
+    ("owner", "o"),
+    ("repository", "p"),
+    ("pull", "n"),
+    ("platform", ""),
+    ("server-url", ""),
+    ("diff", ""),
+    ("reporter", ""),
+    ("min-severity", ""),
+    ("fail-on", ""),
+    ("output", ""),
+    ("fixtures", ""),
+    ("max-tokens", ""),
+    ("max-hunk-tokens", ""),
+    ("budget", ""),
+    ("pr-budget", ""),
+    ("pricing", ""),
+    ("batch-tokens", ""),
+    ("cache", ""),
+    ("baseline", ""),
+    ("samples", ""),
+    ("vote-model", ""),
+    ("quorum", ""),
+    ("record", ""),
+    ("replay", ""),
+];
+
+const FLAGS: &[&str] = &["incremental"];
+
+/// Options that can be repeated, given as inputs separated by newlines or commas.
+const MULTI_OPTIONS: &[&str] = &["reporter", "vote-model"];
+
+/// Reads an input of the action, which GitHub passes as `INPUT_<NAME>`.
+/// Inputs left empty are treated as not given.
+pub fn input(name: &str) -> Option<String> {
+    env::var(format!("INPUT_{}", name.replace('-', "_").to_uppercase()))
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// Appends the options given as inputs of the action to the command line arguments,
+/// so that they are handled exactly like the flags. Flags on the command line win.
+pub fn args_with_inputs(args: Vec<String>) -> Vec<String> {
+    with_inputs(args, input)
+}
+
+fn with_inputs(mut args: Vec<String>, input: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    let given = |name: &str, short: &str| {
+        args.iter().any(|arg| {
+            arg == &format!("--{}", name)
+                || arg.starts_with(&format!("--{}=", name))
+                || (!short.is_empty() && arg.starts_with(&format!("-{}", short)))
+        })
+    };
+
+    let mut extra = Vec::new();
+    for (name, short) in OPTIONS {
+        if let Some(value) = input(name).filter(|_| !given(name, short)) {
+            if MULTI_OPTIONS.contains(name) {
+                extra.extend(
+                    value
+                        .split(['\n', ','])
+                        .map(str::trim)
+                        .filter(|value| !value.is_empty())
+                        .map(|value| format!("--{}={}", name, value)),
+                );
+            } else {
+                extra.push(format!("--{}={}", name, value.trim()));
+            }
+        }
+    }
+    for name in FLAGS {
+        if input(name).is_some_and(|value| value.trim() == "true") && !given(name, "") {
+            extra.push(format!("--{}", name));
+        }
+    }
+
+    args.extend(extra);
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_inputs() {
+        let args = vec![
+            "llm-linter".to_string(),
+            "--reporter=stdout".to_string(),
+            "-n".to_string(),
+            "3".to_string(),
+        ];
+        let inputs = |name: &str| match name {
+            "rules" => Some("docs/rules.md".to_string()),
+            "pull" => Some("4".to_string()),
+            "reporter" => Some("actions".to_string()),
+            "max-tokens" => Some("2000\n".to_string()),
+            "incremental" => Some("true".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            with_inputs(args, inputs),
+            vec![
+                "llm-linter",
+                "--reporter=stdout",
+                "-n",
+                "3",
+                "--rules=docs/rules.md",
+                "--max-tokens=2000",
+                "--incremental",
+            ]
+        );
+
+        let inputs = |name: &str| match name {
+            "reporter" => Some("github\nhtml=report.html, actions\n".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            with_inputs(vec!["llm-linter".to_string()], inputs),
+            vec![
+                "llm-linter",
+                "--reporter=github",
+                "--reporter=html=report.html",
+                "--reporter=actions",
+            ]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use std::fs::{read_to_string, write};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::core::{IndicationKind, Indications};
+
+/// Indications accepted as they are, e.g. found in old code when a rule is introduced,
+/// which are not reported again. They are recognized by their fingerprint, so that they
+/// still match once the lines have moved.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Baseline {
+    indications: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Entry {
+    fingerprint: String,
+    /// The fields below are only for people reading the file.
+    path: String,
+    rule: Option<String>,
+    message: String,
+}
+
+impl Baseline {
+    /// Accepts the indications except the cancelled ones.
+    pub fn new(indications: &Indications) -> Baseline {
+        let mut entries = indications
+            .values
+            .iter()
+            .filter(|indication| indication.kind != IndicationKind::Cancel)
+            .filter_map(|indication| {
+                Some(Entry {
+                    fingerprint: indication.fingerprint.clone()?,
+                    path: indication.location.path.clone(),
+                    rule: indication.rule.clone(),
+                    message: indication.message.clone(),
+                })
+            })
+            .collect::<Vec<Entry>>();
+        // Sorted so that the file changes as little as possible between runs.
+        entries.sort_by(|a, b| (&a.path, &a.fingerprint).cmp(&(&b.path, &b.fingerprint)));
+        entries.dedup_by(|a, b| a.fingerprint == b.fingerprint);
+        Baseline {
+            indications: entries,
+        }
+    }
+
+    pub fn read(path: &str) -> Result<Baseline> {
+        let content =
+            read_to_string(path).with_context(|| format!("Could not read file: {}", path))?;
+        serde_json::from_str(&content).with_context(|| format!("Could not parse file: {}", path))
+    }
+
+    pub fn write(&self, path: &str) -> Result<()> {
+        write(path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Could not write file: {}", path))
+    }
+
+    pub fn len(&self) -> usize {
+        self.indications.len()
+    }
+
+    /// Drops the indications in the baseline.
+    pub fn filter(&self, indications: Indications) -> Indications {
+        let accepted = self
+            .indications
+            .iter()
+            .map(|entry| entry.fingerprint.as_str())
+            .collect::<HashSet<&str>>();
+        Indications {
+            values: indications
+                .values
+                .into_iter()
+                .filter(|indication| {
+                    !indication
+                        .fingerprint
+                        .as_deref()
+                        .is_some_and(|fingerprint| accepted.contains(fingerprint))
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Indication;
+
+    #[test]
+    fn test_filter() {
+        let indication = |line, fingerprint: &str| Indication {
+            message: "Use `expect`".to_string(),
+            rule: Some("for-rust".to_string()),
+            fingerprint: Some(fingerprint.to_string()),
+            ..Indication::for_test("src/main.rs", line, line)
+        };
+        let baseline = Baseline::new(&Indications {
+            values: vec![indication(2, "a"), indication(8, "a")],
+        });
+        assert_eq!(baseline.len(), 1);
+
+        let filtered = baseline.filter(Indications {
+            values: vec![indication(12, "a"), indication(12, "b")],
+        });
+        assert_eq!(filtered.values.len(), 1);
+        assert_eq!(filtered.values[0].fingerprint.as_deref(), Some("b"));
+    }
+}
@@ -0,0 +1,103 @@
+use std::fs::{create_dir_all, read_to_string, rename, write};
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{Context, Result};
+use log::warn;
+use sha2::{Digest, Sha256};
+
+use crate::core::Indication;
+
+/// Persistent cache of LLM responses, one file per key.
+///
+/// Files are written to a temporary name and then renamed into place, so concurrent runs
+/// sharing the directory never read a partially written entry.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+static TEMPORARY_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+impl Cache {
+    pub fn new(dir: PathBuf) -> Cache {
+        Cache { dir }
+    }
+
+    pub fn key(model: &str, prompt_version: u32, rules: &str, hunk: &str) -> String {
+        let rules_hash = Sha256::digest(rules);
+        let hash = Sha256::new()
+            .chain_update(model)
+            .chain_update([0])
+            .chain_update(prompt_version.to_be_bytes())
+            .chain_update(rules_hash)
+            .chain_update(hunk)
+            .finalize();
+        format!("{:x}", hash)
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<Indication>> {
+        let path = self.path_of(key);
+        let content = read_to_string(&path).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(indications) => Some(indications),
+            Err(e) => {
+                warn!("Ignoring broken cache entry {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    pub fn put(&self, key: &str, indications: &[Indication]) -> Result<()> {
+        create_dir_all(&self.dir)
+            .with_context(|| format!("Could not create directory: {}", self.dir.display()))?;
+
+        let temporary_path = self.dir.join(format!(
+            ".{}.{}.{}.tmp",
+            key,
+            process::id(),
+            TEMPORARY_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        write(&temporary_path, serde_json::to_string(indications)?)
+            .with_context(|| format!("Could not write file: {}", temporary_path.display()))?;
+        rename(&temporary_path, self.path_of(key))
+            .with_context(|| format!("Could not rename file: {}", temporary_path.display()))?;
+        Ok(())
+    }
+
+    fn path_of(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::remove_dir_all;
+
+    use super::*;
+
+    #[test]
+    fn test_cache() {
+        let dir = temp_dir().join(format!("llm-linter-cache-{}", process::id()));
+        let cache = Cache::new(dir.clone());
+        let key = Cache::key("gpt-4o", 1, "rules", "hunk");
+
+        assert!(cache.get(&key).is_none());
+        cache
+            .put(
+                &key,
+                &[Indication {
+                    message: "message".to_string(),
+                    ..Indication::for_test("src/main.rs", 1, 1)
+                }],
+            )
+            .unwrap();
+        let cached = cache.get(&key);
+        remove_dir_all(&dir).unwrap();
+
+        assert_eq!(cached.unwrap()[0].message, "message");
+        assert_ne!(key, Cache::key("gpt-4o", 2, "rules", "hunk"));
+        assert_ne!(key, Cache::key("gpt-4o", 1, "other rules", "hunk"));
+    }
+}
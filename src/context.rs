@@ -0,0 +1,131 @@
+use std::env;
+use std::fs::read_to_string;
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+
+/// The pull request a run on GitHub Actions, or the merge request a pipeline on GitLab CI,
+/// was triggered by.
+#[derive(Debug, PartialEq)]
+pub struct PullRequestContext {
+    pub owner: String,
+    pub repository: String,
+    pub pull_number: u64,
+}
+
+/// Reads the context from `GITHUB_REPOSITORY` and the event payload at `GITHUB_EVENT_PATH`.
+/// Returns `None` outside of GitHub Actions.
+pub fn from_actions_env() -> Result<Option<PullRequestContext>> {
+    let (Ok(repository), Ok(event_path)) =
+        (env::var("GITHUB_REPOSITORY"), env::var("GITHUB_EVENT_PATH"))
+    else {
+        return Ok(None);
+    };
+    let event = read_to_string(&event_path)
+        .with_context(|| format!("Could not read file: {}", event_path))?;
+    parse(&repository, &event)
+}
+
+/// Reads the context of a merge request pipeline from the predefined variables of GitLab CI.
+/// Returns `None` outside of GitLab CI or in other pipelines, e.g. for branches.
+/// Ref: https://docs.gitlab.com/ee/ci/variables/predefined_variables.html
+pub fn from_gitlab_ci_env() -> Result<Option<PullRequestContext>> {
+    parse_gitlab_ci(|name| env::var(name).ok())
+}
+
+/// The owner is the namespace of the project, which may contain subgroups.
+fn parse_gitlab_ci(var: impl Fn(&str) -> Option<String>) -> Result<Option<PullRequestContext>> {
+    let (Some(project_path), Some(iid)) = (
+        var("CI_MERGE_REQUEST_PROJECT_PATH"),
+        var("CI_MERGE_REQUEST_IID"),
+    ) else {
+        return Ok(None);
+    };
+    let (owner, repository) = project_path
+        .rsplit_once('/')
+        .ok_or_else(|| anyhow!("Invalid CI_MERGE_REQUEST_PROJECT_PATH: {}", project_path))?;
+
+    Ok(Some(PullRequestContext {
+        owner: owner.to_string(),
+        repository: repository.to_string(),
+        pull_number: iid
+            .parse()
+            .with_context(|| format!("Invalid CI_MERGE_REQUEST_IID: {}", iid))?,
+    }))
+}
+
+/// Returns `None` if the event is not about a pull request, e.g. `push`.
+fn parse(repository: &str, event: &str) -> Result<Option<PullRequestContext>> {
+    let (owner, repository) = repository
+        .split_once('/')
+        .ok_or_else(|| anyhow!("Invalid GITHUB_REPOSITORY: {}", repository))?;
+    let event = serde_json::from_str::<Value>(event).context("Invalid event payload")?;
+
+    let pull_request = &event["pull_request"];
+    let Some(pull_number) = pull_request["number"].as_u64() else {
+        return Ok(None);
+    };
+
+    Ok(Some(PullRequestContext {
+        owner: owner.to_string(),
+        repository: repository.to_string(),
+        pull_number,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let event = indoc! {r#"
+            {
+                "action": "synchronize",
+                "number": 12,
+                "pull_request": {
+                    "number": 12,
+                    "head": { "sha": "0123abc", "ref": "feature" }
+                }
+            }
+        "#};
+
+        assert_eq!(
+            parse("HosokawaR/LLM-linter", event).unwrap(),
+            Some(PullRequestContext {
+                owner: "HosokawaR".to_string(),
+                repository: "LLM-linter".to_string(),
+                pull_number: 12,
+            })
+        );
+        assert_eq!(
+            parse("HosokawaR/LLM-linter", r#"{"ref": "refs/heads/main"}"#).unwrap(),
+            None
+        );
+        assert!(parse("LLM-linter", event).is_err());
+    }
+
+    #[test]
+    fn test_parse_gitlab_ci() {
+        let var = |name: &str| match name {
+            "CI_MERGE_REQUEST_PROJECT_PATH" => Some("group/subgroup/project".to_string()),
+            "CI_MERGE_REQUEST_IID" => Some("7".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            parse_gitlab_ci(var).unwrap(),
+            Some(PullRequestContext {
+                owner: "group/subgroup".to_string(),
+                repository: "project".to_string(),
+                pull_number: 7,
+            })
+        );
+        assert_eq!(
+            parse_gitlab_ci(|name| var(name).filter(|_| name != "CI_MERGE_REQUEST_IID")).unwrap(),
+            None
+        );
+    }
+}
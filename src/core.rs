@@ -1,60 +1,384 @@
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Result};
 use globset::Glob;
 use indoc::formatdoc;
+use log::{info, warn};
 use patch::Patch as Patch_;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tokio::time::sleep;
 
+use crate::cache::Cache;
+use crate::pricing::Pricing;
+use crate::summary::Summary;
+use crate::suppressions::Suppressions;
+use crate::tokens;
+
 pub struct Linter<L: LlmClient> {
     llm_client: L,
     rules: Rules,
+    interval: Duration,
+    cache: Option<Cache>,
+    batch_tokens: usize,
+    pricing: Pricing,
+    budget: Option<f64>,
+    /// Clients of further models asked about each batch besides `llm_client`.
+    other_models: Vec<L>,
+    /// Times each model is asked about each batch.
+    samples: usize,
+    /// Answers that must agree on an indication to keep it.
+    quorum: usize,
+    suppressions: Suppressions,
+    summary: Mutex<Summary>,
 }
 
 const SLEEP: Duration = Duration::from_secs(3);
 
+/// Completion tokens assumed when estimating the cost of a request before sending it.
+const ESTIMATED_COMPLETION_TOKENS: u64 = 1000;
+
+/// Bump this whenever the prompt template changes, so that cached responses are not reused.
+const PROMPT_VERSION: u32 = 5;
+
 impl<L: LlmClient> Linter<L> {
     pub fn new(llm_client: L, rules: Rules) -> Linter<L> {
-        Linter { llm_client, rules }
+        Linter {
+            llm_client,
+            rules,
+            interval: SLEEP,
+            cache: None,
+            batch_tokens: 0,
+            pricing: Pricing::default(),
+            budget: None,
+            other_models: Vec::new(),
+            samples: 1,
+            quorum: 1,
+            suppressions: Suppressions::default(),
+            summary: Mutex::new(Summary::default()),
+        }
+    }
+
+    pub fn with_pricing(mut self, pricing: Pricing) -> Linter<L> {
+        self.pricing = pricing;
+        self
+    }
+
+    /// Stops sending requests once their estimated cost would exceed the budget,
+    /// in the currency of the pricing.
+    pub fn with_budget(mut self, budget: f64) -> Linter<L> {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Sends hunks matching the same rules in one request, as long as their estimated
+    /// tokens fit in the budget. With 0, every hunk is sent on its own.
+    pub fn with_batch_tokens(mut self, batch_tokens: usize) -> Linter<L> {
+        self.batch_tokens = batch_tokens;
+        self
+    }
+
+    /// Asks every model `samples` times about each batch and keeps only the indications
+    /// `quorum` of the answers agree on, by overlapping location and rule, to cut down on
+    /// false positives. `other_models` are asked besides the client the linter was built with.
+    pub fn with_voting(mut self, other_models: Vec<L>, samples: usize, quorum: usize) -> Linter<L> {
+        self.other_models = other_models;
+        self.samples = samples;
+        self.quorum = quorum;
+        self
+    }
+
+    /// Skips the rules disabled on whole hunks by comments in the code, and drops the
+    /// indications on lines where their rule is disabled.
+    pub fn with_suppressions(mut self, suppressions: Suppressions) -> Linter<L> {
+        self.suppressions = suppressions;
+        self
+    }
+
+    pub fn with_cache(mut self, cache: Cache) -> Linter<L> {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Sets the wait between LLM requests, which is only needed against rate-limited APIs.
+    pub fn with_interval(mut self, interval: Duration) -> Linter<L> {
+        self.interval = interval;
+        self
     }
 
     pub async fn lint(&self, patches: Patches) -> Indications {
+        self.lint_with(&self.rules, patches).await
+    }
+
+    /// Lints the patches against the given rules instead of the ones the linter was built with.
+    pub async fn lint_with(&self, rules: &Rules, patches: Patches) -> Indications {
         let mut patch_indications = Vec::new();
+        let mut batches: Vec<Batch> = Vec::new();
 
         for patch in patches.all {
-            let prompt = self.generate_prompt(patch.clone());
-            match prompt {
-                None => {
-                    continue;
+            let rules = self.extract_rules_for(rules, &patch);
+            if rules.is_empty() {
+                continue;
+            }
+
+            let cache_key = Cache::key(
+                &self.cache_model(),
+                PROMPT_VERSION,
+                &rules,
+                &patch.content_with_path(),
+            );
+            if let Some(cached) = self.cache.as_ref().and_then(|c| c.get(&cache_key)) {
+                info!("Cache hit: {} (from line {})", patch.path, patch.start_line);
+                let mut summary = self.summary.lock().unwrap();
+                summary.cache_hits += 1;
+                summary.hunks_linted += 1;
+                patch_indications.extend(fingerprinted(cached, &patch));
+                continue;
+            }
+
+            let tokens = tokens::estimate(self.llm_client.model(), &patch.content_with_path());
+            match batches
+                .iter_mut()
+                .rev()
+                .find(|batch| batch.rules == rules)
+                .filter(|batch| batch.tokens + tokens <= self.batch_tokens)
+            {
+                Some(batch) => {
+                    batch.tokens += tokens;
+                    batch.hunks.push((patch, cache_key));
                 }
-                Some(prompt) => {
-                    sleep(SLEEP).await;
-
-                    match self.llm_client.check(patch.path.clone(), prompt).await {
-                        Ok(result) => {
-                            patch_indications.extend(result);
-                        }
-                        Err(e) => {
-                            panic!("Failed to lint: {}", e);
-                        }
+                None => batches.push(Batch {
+                    rules,
+                    hunks: vec![(patch, cache_key)],
+                    tokens,
+                }),
+            }
+        }
+
+        for (i, batch) in batches.iter().enumerate() {
+            let prompt = self.generate_prompt(&batch.rules, &batch.hunks);
+            let voters = self.voters();
+            if let Some(budget) = self.budget {
+                let cost = voters
+                    .iter()
+                    .map(|voter| {
+                        let estimated = Usage {
+                            prompt_tokens: tokens::estimate(voter.model(), &prompt) as u64,
+                            completion_tokens: ESTIMATED_COMPLETION_TOKENS,
+                        };
+                        self.cost_of(voter.model(), &estimated).unwrap_or_else(|| {
+                            panic!("No price for model {} to enforce the budget", voter.model())
+                        })
+                    })
+                    .sum::<f64>();
+                if self.spent() + cost > budget {
+                    let skipped = batches[i..]
+                        .iter()
+                        .map(|batch| batch.hunks.len())
+                        .sum::<usize>();
+                    warn!(
+                        "Stopped linting to stay within the budget of {:.4} {} ({:.4} spent), skipped {} hunks",
+                        budget,
+                        self.pricing.currency,
+                        self.spent(),
+                        skipped
+                    );
+                    self.summary.lock().unwrap().hunks_skipped += skipped;
+                    break;
+                }
+            }
+
+            // Indications of each hunk, as answered by each voter.
+            let mut votes: Vec<Vec<Vec<Indication>>> =
+                batch.hunks.iter().map(|_| Vec::new()).collect();
+            for voter in voters {
+                sleep(self.interval).await;
+
+                let started_at = Instant::now();
+                let completion = voter
+                    .check(prompt.clone())
+                    .await
+                    .unwrap_or_else(|e| panic!("Failed to lint: {}", e));
+                let cost = self.cost_of(voter.model(), &completion.usage);
+                match cost {
+                    Some(cost) => info!("Cost: {:.4} {}", cost, self.pricing.currency),
+                    None => warn!("No price for model {}", voter.model()),
+                }
+                self.summary.lock().unwrap().record_request(
+                    voter.model(),
+                    &completion.usage,
+                    cost,
+                    completion.retries,
+                    started_at.elapsed(),
+                );
+                for (hunk_votes, indications) in
+                    votes.iter_mut().zip(batch.assign(completion.indications))
+                {
+                    hunk_votes.push(indications);
+                }
+            }
+            self.summary.lock().unwrap().hunks_linted += batch.hunks.len();
+
+            for ((patch, cache_key), hunk_votes) in batch.hunks.iter().zip(votes) {
+                let (indications, outvoted) = vote(hunk_votes, self.quorum);
+                let indications = fingerprinted(indications, patch);
+                if outvoted > 0 {
+                    info!("Dropped {} indications short of the quorum", outvoted);
+                    self.summary.lock().unwrap().outvoted += outvoted;
+                }
+                if let Some(cache) = &self.cache {
+                    if let Err(e) = cache.put(cache_key, &indications) {
+                        warn!("Failed to write cache: {}", e);
                     }
                 }
+                patch_indications.extend(indications);
             }
         }
 
+        let count = patch_indications.len();
+        patch_indications.retain(|indication| !self.suppressions.suppresses(indication));
+        if patch_indications.len() < count {
+            info!(
+                "Suppressed {} indications by comments in the code",
+                count - patch_indications.len()
+            );
+        }
+
+        for indication in &mut patch_indications {
+            if let Some(severity) = rules.severity_for(indication) {
+                indication.kind = severity.apply(indication.kind);
+            }
+        }
+
+        // Overlapping windows of a split hunk may report the same finding twice. Findings
+        // sharing lines but differing in rule or message are distinct.
+        let mut seen = Vec::new();
+        patch_indications.retain(|indication: &Indication| {
+            let key = (
+                indication.location.path.clone(),
+                indication.location.start_line,
+                indication.location.end_line,
+                indication.kind,
+                indication.rule.clone(),
+                indication.message.clone(),
+            );
+            let duplicated = seen.contains(&key);
+            seen.push(key);
+            !duplicated
+        });
+
         Indications {
             values: patch_indications,
         }
     }
 
-    fn generate_prompt(&self, patch: Patch) -> Option<String> {
-        let rules = self.extract_rules_for(&patch);
-        if rules.is_empty() {
-            return None;
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Cost of the requests sent so far, in the currency of the pricing.
+    pub fn spent(&self) -> f64 {
+        self.summary.lock().unwrap().cost()
+    }
+
+    pub fn currency(&self) -> &str {
+        &self.pricing.currency
+    }
+
+    /// Statistics of everything planned and linted so far.
+    pub fn summary(&self, indications: &Indications) -> Summary {
+        self.summary
+            .lock()
+            .unwrap()
+            .clone()
+            .finish(&self.pricing.currency, indications)
+    }
+
+    fn cost_of(&self, model: &str, usage: &Usage) -> Option<f64> {
+        self.pricing.cost_of(model, usage)
+    }
+
+    /// A model asked about the batches that has no price, so that no budget can be enforced.
+    pub fn unpriced_model(&self) -> Option<&str> {
+        self.voters()
+            .into_iter()
+            .map(|voter| voter.model())
+            .find(|model| self.pricing.price_of(model).is_none())
+    }
+
+    /// Clients asked about each batch, once per sample.
+    fn voters(&self) -> Vec<&L> {
+        std::iter::once(&self.llm_client)
+            .chain(&self.other_models)
+            .flat_map(|client| std::iter::repeat_n(client, self.samples))
+            .collect()
+    }
+
+    /// Identifies the voters in cache keys, since the votes decide the indications cached.
+    fn cache_model(&self) -> String {
+        if self.voters().len() == 1 {
+            return self.llm_client.model().to_string();
+        }
+        let models = std::iter::once(&self.llm_client)
+            .chain(&self.other_models)
+            .map(|client| client.model())
+            .collect::<Vec<&str>>()
+            .join("+");
+        format!("{} x{} quorum {}", models, self.samples, self.quorum)
+    }
+
+    /// Splits hunks larger than `budget.hunk` tokens into overlapping windows and picks
+    /// as many of them as fit in `budget.total` tokens. Hunks of files matched by more
+    /// rules are picked first; hunks matched by no rule cost nothing and are kept.
+    pub fn plan(&self, patches: Patches, budget: &TokenBudget) -> Plan {
+        let model = self.llm_client.model();
+        let hunks = patches
+            .all
+            .iter()
+            .flat_map(|patch| patch.split(model, budget.hunk))
+            .map(|patch| {
+                let matched_rules = self
+                    .rules
+                    .all
+                    .iter()
+                    .filter(|rule| rule.matches(&patch.path))
+                    .count();
+                let tokens = tokens::estimate(model, &patch.content_with_path());
+                (patch, matched_rules, tokens)
+            })
+            .collect::<Vec<(Patch, usize, usize)>>();
+
+        let mut by_priority = (0..hunks.len()).collect::<Vec<usize>>();
+        by_priority.sort_by_key(|&i| std::cmp::Reverse(hunks[i].1));
+        let mut picked = vec![false; hunks.len()];
+        let mut total = 0;
+        for i in by_priority {
+            let (_, matched_rules, tokens) = &hunks[i];
+            if *matched_rules == 0 || total + tokens <= budget.total {
+                picked[i] = true;
+                total += if *matched_rules == 0 { 0 } else { *tokens };
+            }
+        }
+
+        let mut plan = Plan {
+            linted: Patches { all: Vec::new() },
+            skipped: Patches { all: Vec::new() },
+            tokens: total,
+        };
+        self.summary.lock().unwrap().hunks_skipped += picked.iter().filter(|p| !**p).count();
+        for ((patch, _, _), picked) in hunks.into_iter().zip(picked) {
+            if picked {
+                plan.linted.all.push(patch);
+            } else {
+                plan.skipped.all.push(patch);
+            }
         }
+        plan
+    }
 
-        Some(formatdoc! {r#"
+    fn generate_prompt(&self, rules: &str, hunks: &[(Patch, String)]) -> String {
+        formatdoc! {r#"
             これから提示する Git パッチに対して以下のルールに違反している可能性がある箇所を見つけ、検証しましょう。
 
             以下のレビューで言及されたルールについてのみ検証しなさい。
@@ -63,13 +387,17 @@ impl<L: LlmClient> Linter<L> {
             locations には指摘箇所の行番号を記載しなさい。
             quotation には指摘箇所のコードの重要部分を一部抜粋しなさい。
             reference には指摘の根拠となるルールを簡単に引用しなさい。
+            rule には指摘の根拠となるルールの名前を記載しなさい。各ルールの名前はルールの直前の <!-- rule: 名前 --> に書かれています。
             suspiciousReason には指摘箇所が疑わしい理由を記載しなさい。
             noProblemReason には指摘箇所が問題ない理由を記載しなさい。
             revaluation には suspiciousReason と noProblemReason を踏まえて指摘が適切か再評価しなさい。指摘がパッチから読み取れない推測に基づいている場合は、指摘は適切でないと判断しなさい。
             message には revaluation を踏まえて指摘内容を記載しなさい。
+            suggestion には修正案があれば、start_line から end_line までの行全体を置き換える修正後のコードを、行番号と「+」を除いて記載しなさい。修正案がなければ null にしなさい。
             kind では
             指摘がいかなる場合も適切ならば kind を "error" にしなさい。
             指摘が場合によっては適切であるかもしれない場合は kind を "warning" にしなさい。
+            指摘がルールへの違反ではなく、軽微な改善の提案にとどまる場合は kind を "info" にしなさい。
+            指摘が参考情報にすぎず、修正の必要がない場合は kind を "hint" にしなさい。
             指摘が適切でないことが分かった場合は kind を "cancel" に変更しなさい。
 
             ソースコードの左端の番号は行番号を表しています。
@@ -84,16 +412,19 @@ impl<L: LlmClient> Linter<L> {
             {{
                 messages: {{
                     "location": {{
+                        "path": string,
                         "start_line": number
                         "end_line": number
                     }},
                     "quotation": string,
                     "reference": string,
+                    "rule": string,
                     "suspiciousReason": string,
                     "noProblemReason": string,
                     "reevalution": string,
                     "message": string,
-                    "kind": "error" | "warning" | "cancel"
+                    "suggestion": string | null,
+                    "kind": "error" | "warning" | "info" | "hint" | "cancel"
                 }}[]
             }}
 
@@ -102,63 +433,141 @@ impl<L: LlmClient> Linter<L> {
             {{
                 messages: [
                     {{
-                        location: {{ start_line: 10, end_line: 10 }},
+                        location: {{ path: "src/xxx.ts", start_line: 10, end_line: 10 }},
                         quotation: "const huge = XXXX();",
                         reference: "XXX の代わりに YYY を使うというルールがあります。"
+                        rule: "domain"
                         suspiciousReason: "XXX は YYY に変更するべきだが、変更されていない。"
                         noProblemReason: "XXX は YYY に変更する必要がない。"
                         reevalution: "実際に XXX が使用されているので、YYY に変更する必要があり、コードは誤っているので、この指摘は適切である。",
                         message: "XXX の箇所は YYY に変更してください。",
+                        suggestion: "const huge = YYYY();",
                         kind: "error"
                     }},
                     {{
-                        location: {{ start_line: 20, end_line: 30 }},
+                        location: {{ path: "src/zzz.ts", start_line: 20, end_line: 30 }},
                         quotation: "const huge = ZZZZ();",
                         reference: "ZZZ をつけるというルールがあります。"
+                        rule: "ui"
                         suspiciousReason: "ZZZ をつけるというルールが有る。"
                         noProblemReason: "ZZZ はついている。"
                         reevalution: "実際に ZZZ はついているので、コードは正しく、この指摘は不適切である。"
                         message: "ZZZ がついているので問題ありません。",
+                        suggestion: null,
                         kind: "cancel"
                     }},
                 ]
             }}
 
             以下が Git のパッチです。
+            パッチは複数のファイルにまたがることがあります。location.path には指摘箇所のパッチの path をそのまま記載しなさい。
 
             {}
 
             "#,
             rules,
-            patch.content_with_path(),
-        })
+            hunks
+                .iter()
+                .map(|(patch, _)| patch.content_with_path())
+                .collect::<Vec<String>>()
+                .join("\n\n"),
+        }
     }
 
-    fn extract_rules_for(&self, patch: &Patch) -> String {
-        self.rules
+    fn extract_rules_for(&self, rules: &Rules, patch: &Patch) -> String {
+        rules
             .all
             .iter()
-            .filter(|rule| {
-                Glob::new(&rule.target_file_glob)
-                    .unwrap_or_else(|_| panic!("Invalid glob: {}", rule.target_file_glob))
-                    .compile_matcher()
-                    .is_match(&patch.path)
-            })
-            .map(|rule| rule.content.clone())
+            .filter(|rule| rule.matches(&patch.path))
+            .filter(|rule| !self.suppressions.disables(patch, &rule.name))
+            .map(|rule| format!("<!-- rule: {} -->\n{}", rule.name, rule.content.trim()))
             .collect::<Vec<String>>()
-            .join("\n")
+            .join("\n\n")
             .trim()
             .to_string()
     }
 }
 
+/// Hunks sharing the same rules, linted in a single request.
+struct Batch {
+    rules: String,
+    /// Hunks and their cache keys.
+    hunks: Vec<(Patch, String)>,
+    tokens: usize,
+}
+
+impl Batch {
+    /// Splits the indications of the response by the hunk they point to.
+    fn assign(&self, indications: Vec<Indication>) -> Vec<Vec<Indication>> {
+        let mut assigned: Vec<Vec<Indication>> = self.hunks.iter().map(|_| Vec::new()).collect();
+
+        for mut indication in indications {
+            let Some(path) = self.resolve_path(&indication.location.path) else {
+                warn!(
+                    "Dropping indication for unknown path: {:?}",
+                    indication.location
+                );
+                continue;
+            };
+            let index = self
+                .hunks
+                .iter()
+                .position(|(patch, _)| {
+                    patch.path == path
+                        && patch.start_line <= indication.location.end_line
+                        && indication.location.start_line <= patch.end_line
+                })
+                .or_else(|| self.hunks.iter().position(|(patch, _)| patch.path == path))
+                .unwrap();
+            indication.location.path = path;
+            assigned[index].push(indication);
+        }
+
+        assigned
+    }
+
+    /// Maps the path the model answered with to a path of the batch, tolerating
+    /// a missing path or a stray prefix such as `b/`.
+    fn resolve_path(&self, path: &str) -> Option<String> {
+        let paths = self
+            .hunks
+            .iter()
+            .map(|(patch, _)| patch.path.as_str())
+            .collect::<Vec<&str>>();
+        paths
+            .iter()
+            .find(|p| **p == path)
+            .or_else(|| {
+                paths
+                    .iter()
+                    .find(|p| !path.is_empty() && (p.ends_with(path) || path.ends_with(**p)))
+            })
+            .or_else(|| paths.iter().all(|p| *p == paths[0]).then(|| &paths[0]))
+            .map(|p| p.to_string())
+    }
+}
+
 pub trait LlmClient {
-    fn new(api_key: String, model: String) -> Self;
-    async fn check(&self, path: String, prompt: String) -> Result<Vec<Indication>>;
+    fn model(&self) -> &str;
+    /// Indications must carry the path the model answered with, which may be empty.
+    async fn check(&self, prompt: String) -> Result<Completion>;
+}
+
+pub struct Completion {
+    pub indications: Vec<Indication>,
+    pub usage: Usage,
+    /// Number of failed attempts before the successful one.
+    pub retries: u32,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
 }
 
 pub trait Reporter {
-    async fn report(&self, indications: Indications);
+    async fn report(&self, indications: &Indications, summary: &Summary) -> Result<()>;
 }
 
 pub trait PatchReader {
@@ -176,11 +585,16 @@ impl Patches {
                 .flat_map(|patch| {
                     patch.hunks.iter().map(|hunk| Patch {
                         path: patch.new.path.to_string().replace("b/", ""),
+                        old_path: match patch.old.path.strip_prefix("a/") {
+                            Some(old_path) => old_path.to_string(),
+                            // A new file has no old path.
+                            None => patch.new.path.to_string().replace("b/", ""),
+                        },
                         content: hunk
                             .lines
                             .iter()
                             .map(|line| match line {
-                                patch::Line::Context(s) => s.to_string(),
+                                patch::Line::Context(s) => format!(" {}", s),
                                 patch::Line::Add(s) => format!("+{}", s),
                                 patch::Line::Remove(s) => format!("-{}", s),
                             })
@@ -192,17 +606,29 @@ impl Patches {
                             .join("\n"),
                         start_line: hunk.new_range.start,
                         end_line: hunk.new_range.start + hunk.new_range.count,
+                        old_start_line: hunk.old_range.start,
+                        hunk: None,
                     })
                 })
                 .collect(),
         })
     }
 
-    pub fn total_lines(&self) -> u64 {
-        self.all
-            .iter()
-            .map(|patch| patch.content.lines().count() as u64)
-            .sum()
+    /// Keeps the hunks overlapping any hunk of `changes` on the new side.
+    pub fn touched_by(self, changes: &Patches) -> Patches {
+        Patches {
+            all: self
+                .all
+                .into_iter()
+                .filter(|patch| {
+                    changes.all.iter().any(|change| {
+                        change.path == patch.path
+                            && change.start_line <= patch.end_line
+                            && patch.start_line <= change.end_line
+                    })
+                })
+                .collect(),
+        }
     }
 }
 
@@ -211,71 +637,391 @@ pub struct Patches {
     pub all: Vec<Patch>,
 }
 
+pub struct TokenBudget {
+    /// Estimated tokens of all hunks to lint in a run.
+    pub total: usize,
+    /// Estimated tokens above which a hunk is split into windows.
+    pub hunk: usize,
+}
+
+pub struct Plan {
+    pub linted: Patches,
+    pub skipped: Patches,
+    /// Estimated tokens of the linted hunks.
+    pub tokens: usize,
+}
+
 #[derive(Clone, Debug)]
 pub struct Patch {
     pub path: String,
+    /// Path of the file before the change, which differs from `path` for renamed files.
+    pub old_path: String,
     pub content: String,
     pub start_line: u64,
     pub end_line: u64,
+    /// Line of the old file the first line of the content is at.
+    pub old_start_line: u64,
+    /// Hunk a window was split from, or `None` for a whole hunk.
+    pub hunk: Option<Arc<Patch>>,
 }
 
+/// Kind of a line of a hunk, given by its first character: ` `, `+` or `-`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// Lines shared by adjacent windows of a split hunk, so that findings spanning the
+/// boundary are still seen as a whole.
+const WINDOW_OVERLAP_LINES: usize = 5;
+
 impl Patch {
     pub fn content_with_path(&self) -> String {
         formatdoc! {r#"path:{}\n{}"#, self.path, self.content}
     }
+
+    /// Lines of the content with the numbers they were shown to the model with, which
+    /// indications refer to.
+    pub fn numbered_lines(&self) -> Vec<(u64, String)> {
+        self.content
+            .lines()
+            .filter_map(|line| {
+                let (number, text) = line.trim_start().split_once(' ')?;
+                Some((number.parse().ok()?, text.to_string()))
+            })
+            .collect()
+    }
+
+    /// Hunk the patch is a window of, or the patch itself if it was not split.
+    pub fn whole(&self) -> &Patch {
+        self.hunk.as_deref().unwrap_or(self)
+    }
+
+    /// Numbered lines with their kind and their code, without the marker of the diff.
+    pub fn diff_lines(&self) -> Vec<(u64, LineKind, String)> {
+        self.numbered_lines()
+            .into_iter()
+            .map(|(number, text)| {
+                let mut chars = text.chars();
+                let kind = match chars.next() {
+                    Some('+') => LineKind::Added,
+                    Some('-') => LineKind::Removed,
+                    _ => LineKind::Context,
+                };
+                (number, kind, chars.as_str().to_string())
+            })
+            .collect()
+    }
+
+    /// Lines of the old and the new file a numbered line of the whole hunk is at, each
+    /// `None` for lines only added or only removed.
+    pub fn sides_of(&self, number: u64) -> Option<(Option<u64>, Option<u64>)> {
+        let (mut old_line, mut new_line) = (self.old_start_line, self.start_line);
+        for (line_number, kind, _) in self.diff_lines() {
+            let sides = match kind {
+                LineKind::Added => (None, Some(new_line)),
+                LineKind::Removed => (Some(old_line), None),
+                LineKind::Context => (Some(old_line), Some(new_line)),
+            };
+            if line_number == number {
+                return Some(sides);
+            }
+            old_line += sides.0.is_some() as u64;
+            new_line += sides.1.is_some() as u64;
+        }
+        None
+    }
+
+    /// Splits the hunk into overlapping windows of at most `max_tokens` estimated tokens.
+    /// A single line longer than that still makes up a window of its own.
+    pub fn split(&self, model: &str, max_tokens: usize) -> Vec<Patch> {
+        if tokens::estimate(model, &self.content) <= max_tokens {
+            return vec![self.clone()];
+        }
+
+        let lines = self.content.lines().collect::<Vec<&str>>();
+        let hunk = Arc::new(self.whole().clone());
+        let mut windows = Vec::new();
+        let mut start = 0;
+        loop {
+            let mut end = start;
+            let mut window_tokens = 0;
+            while end < lines.len() {
+                let line_tokens = tokens::estimate(model, lines[end]) + 1;
+                if end > start && window_tokens + line_tokens > max_tokens {
+                    break;
+                }
+                window_tokens += line_tokens;
+                end += 1;
+            }
+            windows.push(Patch {
+                path: self.path.clone(),
+                old_path: self.old_path.clone(),
+                content: lines[start..end].join("\n"),
+                start_line: self.start_line + start as u64,
+                end_line: self.start_line + end as u64,
+                old_start_line: self.old_start_line
+                    + self.diff_lines()[..start]
+                        .iter()
+                        .filter(|(_, kind, _)| *kind != LineKind::Added)
+                        .count() as u64,
+                hunk: Some(hunk.clone()),
+            });
+            if end == lines.len() {
+                return windows;
+            }
+            start = end.saturating_sub(WINDOW_OVERLAP_LINES).max(start + 1);
+        }
+    }
 }
 
+/// Sets the fingerprints of the indications assigned to the hunk.
+fn fingerprinted(mut indications: Vec<Indication>, patch: &Patch) -> Vec<Indication> {
+    for indication in &mut indications {
+        indication.fingerprint = Some(indication.fingerprint_in(patch.whole()));
+    }
+    indications
+}
+
+/// Keeps the indications found in at least `quorum` of the answers about a hunk, matched
+/// by rule and overlapping lines, with the least severe kind the answers agree on. The
+/// cancelled indications of the first answer are kept as they are. Also returns the
+/// number of indications dropped.
+fn vote(answers: Vec<Vec<Indication>>, quorum: usize) -> (Vec<Indication>, usize) {
+    if answers.len() == 1 {
+        return (answers.into_iter().next().unwrap(), 0);
+    }
+
+    let mut kept = Vec::new();
+    // Indications with the answers agreeing on them.
+    let mut candidates: Vec<(Indication, Vec<usize>)> = Vec::new();
+    for (answer, indications) in answers.into_iter().enumerate() {
+        for indication in indications {
+            if indication.kind == IndicationKind::Cancel {
+                if answer == 0 {
+                    kept.push(indication);
+                }
+                continue;
+            }
+            let agreeing = candidates.iter_mut().find(|(candidate, answers)| {
+                !answers.contains(&answer)
+                    && candidate.rule == indication.rule
+                    && candidate.location.path == indication.location.path
+                    && candidate.location.start_line <= indication.location.end_line
+                    && indication.location.start_line <= candidate.location.end_line
+            });
+            match agreeing {
+                Some((candidate, answers)) => {
+                    candidate.kind = candidate.kind.min(indication.kind);
+                    answers.push(answer);
+                }
+                None => candidates.push((indication, vec![answer])),
+            }
+        }
+    }
+
+    let (agreed, outvoted): (Vec<_>, Vec<_>) = candidates
+        .into_iter()
+        .partition(|(_, answers)| answers.len() >= quorum);
+    kept.extend(agreed.into_iter().map(|(indication, _)| indication));
+    (kept, outvoted.len())
+}
+
+#[derive(Clone)]
 pub struct Rules {
     pub all: Vec<Rule>,
 }
 
+impl Rules {
+    /// Severity of the rule the indication is based on. Without the name of the rule
+    /// from the model, the rule is known only if it is the only one matching the path.
+    fn severity_for(&self, indication: &Indication) -> Option<RuleSeverity> {
+        let rule = match &indication.rule {
+            Some(name) => self.all.iter().find(|rule| &rule.name == name),
+            None => {
+                let mut matching = self
+                    .all
+                    .iter()
+                    .filter(|rule| rule.matches(&indication.location.path));
+                matching.next().filter(|_| matching.next().is_none())
+            }
+        };
+        rule.and_then(|rule| rule.severity)
+    }
+}
+
+#[derive(Clone)]
 pub struct Rule {
+    pub name: String,
     pub target_file_glob: String,
     pub content: String,
+    /// Severity configured in the rule file, applied to the kind chosen by the model.
+    pub severity: Option<RuleSeverity>,
 }
 
-#[derive(Deserialize)]
-pub struct Indications {
-    pub values: Vec<Indication>,
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RuleSeverity {
+    /// Replaces the kind chosen by the model.
+    Override(IndicationKind),
+    /// Lowers the kind chosen by the model to at most this one.
+    Cap(IndicationKind),
 }
 
-impl Indications {
-    pub fn exclude_cancel(self) -> Indications {
-        Indications {
-            values: self
-                .values
-                .into_iter()
-                .filter(|indication| indication.kind != IndicationKind::Cancel)
-                .collect(),
+impl RuleSeverity {
+    /// Cancelled indications stay cancelled.
+    pub fn apply(&self, kind: IndicationKind) -> IndicationKind {
+        match (self, kind) {
+            (_, IndicationKind::Cancel) => IndicationKind::Cancel,
+            (RuleSeverity::Override(severity), _) => *severity,
+            (RuleSeverity::Cap(severity), kind) => kind.min(*severity),
         }
     }
+}
 
-    pub fn exclude_warnings(self) -> Indications {
+impl Rule {
+    pub fn matches(&self, path: &str) -> bool {
+        Glob::new(&self.target_file_glob)
+            .unwrap_or_else(|_| panic!("Invalid glob: {}", self.target_file_glob))
+            .compile_matcher()
+            .is_match(path)
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Indications {
+    pub values: Vec<Indication>,
+}
+
+impl Indications {
+    /// Keeps the indications at least as severe as `kind`.
+    pub fn at_least(&self, kind: IndicationKind) -> Indications {
         Indications {
             values: self
                 .values
-                .into_iter()
-                .filter(|indication| indication.kind != IndicationKind::Warning)
+                .iter()
+                .filter(|indication| indication.kind >= kind)
+                .cloned()
                 .collect(),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Indication {
     pub kind: IndicationKind,
     pub message: String,
     pub location: Location,
+    /// Name of the rule the indication is based on, as answered by the model.
+    #[serde(default)]
+    pub rule: Option<String>,
+    /// Code replacing the lines of the location as a whole.
+    #[serde(default)]
+    pub suggestion: Option<String>,
+    /// How the model came to the indication.
+    #[serde(default)]
+    pub reasoning: Option<Reasoning>,
+    /// Recognizes the same indication across runs, commits and reporters, since it does
+    /// not depend on line numbers. Set by the linter.
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+}
+
+impl Indication {
+    /// Hashes the path, the rule and the flagged lines of the hunk without differences in
+    /// whitespace, so that it stays the same when the lines move. The quotation of the model
+    /// is not used since it varies between answers. `hunk` is the whole hunk the indication
+    /// was found in, since windows overlap and line numbers may repeat across hunks.
+    fn fingerprint_in(&self, hunk: &Patch) -> String {
+        let flagged_code = self.flagged_code(hunk);
+        let code = flagged_code
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let hash = Sha256::new()
+            .chain_update(&self.location.path)
+            .chain_update([0])
+            .chain_update(self.rule.as_deref().unwrap_or(""))
+            .chain_update([0])
+            .chain_update(code)
+            .finalize();
+        format!("{:x}", hash)
+    }
+
+    fn flagged_code(&self, hunk: &Patch) -> String {
+        let location = &self.location;
+        hunk.diff_lines()
+            .into_iter()
+            .filter(|(number, kind, _)| {
+                location.start_line <= *number
+                    && *number <= location.end_line
+                    && *kind != LineKind::Removed
+            })
+            .map(|(_, _, code)| code)
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[cfg(test)]
+impl Indication {
+    /// An error without a message or rule on the lines of `path`, for tests to fill in
+    /// the fields they need with the struct update syntax.
+    pub fn for_test(path: &str, start_line: u64, end_line: u64) -> Indication {
+        Indication {
+            kind: IndicationKind::Error,
+            message: String::new(),
+            location: Location {
+                path: path.to_string(),
+                start_line,
+                end_line,
+            },
+            rule: None,
+            suggestion: None,
+            reasoning: None,
+            fingerprint: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Reasoning {
+    /// Excerpt of the flagged code.
+    pub quotation: String,
+    /// Excerpt of the rule.
+    pub reference: String,
+    pub suspicious_reason: String,
+    pub no_problem_reason: String,
+    /// Weighs the two reasons above, deciding the kind of the indication.
+    pub reevaluation: String,
+}
+
+/// Ordered from the least to the most severe.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum IndicationKind {
-    Error,
-    Warning,
     Cancel,
+    Hint,
+    Info,
+    Warning,
+    Error,
+}
+
+impl IndicationKind {
+    /// Parses the name of a kind as given on the command line.
+    pub fn parse(name: &str) -> Result<IndicationKind> {
+        match name {
+            "error" => Ok(IndicationKind::Error),
+            "warning" => Ok(IndicationKind::Warning),
+            "info" => Ok(IndicationKind::Info),
+            "hint" => Ok(IndicationKind::Hint),
+            "cancel" => Ok(IndicationKind::Cancel),
+            _ => Err(anyhow!("Unknown severity: {}", name)),
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Location {
     pub path: String,
     pub start_line: u64,
@@ -287,3 +1033,249 @@ impl Location {
         self.start_line == self.end_line
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn test_rule_severity() {
+        let cap = RuleSeverity::Cap(IndicationKind::Info);
+        assert_eq!(cap.apply(IndicationKind::Error), IndicationKind::Info);
+        assert_eq!(cap.apply(IndicationKind::Hint), IndicationKind::Hint);
+        assert_eq!(cap.apply(IndicationKind::Cancel), IndicationKind::Cancel);
+
+        let raise = RuleSeverity::Override(IndicationKind::Error);
+        assert_eq!(raise.apply(IndicationKind::Warning), IndicationKind::Error);
+        assert_eq!(raise.apply(IndicationKind::Cancel), IndicationKind::Cancel);
+    }
+
+    #[test]
+    fn test_touched_by() {
+        let patches = Patches::parse(indoc! {"
+            --- a/src/main.rs
+            +++ b/src/main.rs
+            @@ -1,3 +1,4 @@
+             fn main() {
+            +    setup();
+                 run();
+             }
+            @@ -20,3 +21,4 @@
+             fn run() {
+            +    work();
+                 finish();
+             }
+            --- a/src/lib.rs
+            +++ b/src/lib.rs
+            @@ -1,1 +1,2 @@
+             mod core;
+            +mod cache;
+        "})
+        .unwrap();
+        let changes = Patches::parse(indoc! {"
+            --- a/src/main.rs
+            +++ b/src/main.rs
+            @@ -21,2 +21,3 @@
+             fn run() {
+            +    work();
+                 finish();
+        "})
+        .unwrap();
+
+        let touched = patches.touched_by(&changes);
+
+        assert_eq!(touched.all.len(), 1);
+        assert_eq!(touched.all[0].path, "src/main.rs");
+        assert_eq!(touched.all[0].start_line, 21);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let hunk = |start: u64, code: &str| {
+            Patches::parse(&format!(
+                indoc! {"
+                    --- a/src/main.rs
+                    +++ b/src/main.rs
+                    @@ -{0},2 +{0},3 @@
+                     fn main() {{
+                    +{1}
+                     }}
+                "},
+                start, code
+            ))
+            .unwrap()
+            .all
+            .remove(0)
+        };
+        let indication = |line, quotation: &str| Indication {
+            rule: Some("for-rust".to_string()),
+            reasoning: Some(Reasoning {
+                quotation: quotation.to_string(),
+                ..Default::default()
+            }),
+            ..Indication::for_test("src/main.rs", line, line)
+        };
+
+        // Moved lines, whitespace and the quotation do not matter, other code does.
+        let fingerprint =
+            indication(2, "y.unwrap()").fingerprint_in(&hunk(1, "    let x = y.unwrap();"));
+        assert_eq!(
+            indication(12, "let x = y.unwrap()")
+                .fingerprint_in(&hunk(11, "\tlet x =  y.unwrap();")),
+            fingerprint
+        );
+        assert_ne!(
+            indication(12, "y.unwrap()").fingerprint_in(&hunk(11, "    let x = z.unwrap();")),
+            fingerprint
+        );
+        assert_ne!(
+            indication(1, "y.unwrap()").fingerprint_in(&hunk(1, "    let x = y.unwrap();")),
+            fingerprint
+        );
+
+        // Lines shared by overlapping windows are hashed once, as in the whole hunk.
+        let whole = Patches::parse(&format!(
+            "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -0,0 +1,20 @@\n{}\n",
+            (1..=20)
+                .map(|i| format!("+let x{} = {};", i, i))
+                .collect::<Vec<String>>()
+                .join("\n")
+        ))
+        .unwrap()
+        .all
+        .remove(0);
+        let windows = whole.split("gpt-4o", 50);
+        assert!(windows.len() > 2);
+        let indication = Indication::for_test("src/main.rs", 6, 9);
+        let expected = indication.fingerprint_in(&whole);
+        for window in &windows {
+            assert_eq!(indication.fingerprint_in(window.whole()), expected);
+        }
+    }
+
+    #[test]
+    fn test_vote() {
+        let indication = |kind, start_line, end_line, rule: &str| Indication {
+            kind,
+            rule: Some(rule.to_string()),
+            ..Indication::for_test("src/main.rs", start_line, end_line)
+        };
+        let answers = vec![
+            vec![
+                indication(IndicationKind::Error, 3, 4, "for-rust"),
+                indication(IndicationKind::Error, 10, 10, "for-rust"),
+                indication(IndicationKind::Cancel, 20, 20, "naming"),
+            ],
+            vec![
+                indication(IndicationKind::Warning, 4, 5, "for-rust"),
+                indication(IndicationKind::Error, 10, 10, "naming"),
+            ],
+            vec![indication(IndicationKind::Cancel, 3, 3, "for-rust")],
+        ];
+
+        let (kept, outvoted) = vote(answers, 2);
+
+        assert_eq!(outvoted, 2);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0].kind, IndicationKind::Cancel);
+        assert_eq!(kept[0].location.start_line, 20);
+        assert_eq!(kept[1].kind, IndicationKind::Warning);
+        assert_eq!(kept[1].location.start_line, 3);
+    }
+
+    #[test]
+    fn test_batch_assign() {
+        let patches = Patches::parse(indoc! {"
+            --- a/src/main.rs
+            +++ b/src/main.rs
+            @@ -1,2 +1,3 @@
+             fn main() {
+            +    setup();
+             }
+            @@ -20,2 +21,3 @@
+             fn run() {
+            +    work();
+             }
+            --- a/src/lib.rs
+            +++ b/src/lib.rs
+            @@ -1,1 +1,2 @@
+             mod core;
+            +mod cache;
+        "})
+        .unwrap();
+        let batch = Batch {
+            rules: "rules".to_string(),
+            hunks: patches
+                .all
+                .into_iter()
+                .map(|patch| (patch, String::new()))
+                .collect(),
+            tokens: 0,
+        };
+        let indication = |path: &str, line: u64| Indication::for_test(path, line, line);
+
+        let assigned = batch.assign(vec![
+            indication("src/main.rs", 22),
+            indication("b/src/lib.rs", 2),
+            indication("src/unknown.rs", 1),
+        ]);
+
+        assert_eq!(assigned[0].len(), 0);
+        assert_eq!(assigned[1].len(), 1);
+        assert_eq!(assigned[2].len(), 1);
+        assert_eq!(assigned[2][0].location.path, "src/lib.rs");
+    }
+
+    #[test]
+    fn test_sides_of() {
+        let patches = Patches::parse(indoc! {"
+            --- a/README.md
+            +++ b/README.md
+            @@ -3,3 +3,3 @@
+             - first
+            -- second
+            +- 2nd
+             - third
+        "})
+        .unwrap();
+        let patch = &patches.all[0];
+
+        // Context lines starting with `-` or `+` are not taken for removed or added ones.
+        assert_eq!(patch.sides_of(3), Some((Some(3), Some(3))));
+        assert_eq!(patch.sides_of(4), Some((Some(4), None)));
+        assert_eq!(patch.sides_of(5), Some((None, Some(4))));
+        assert_eq!(patch.sides_of(6), Some((Some(5), Some(5))));
+        assert_eq!(patch.sides_of(7), None);
+    }
+
+    #[test]
+    fn test_split() {
+        let patch = Patch {
+            path: "src/main.rs".to_string(),
+            old_path: "src/main.rs".to_string(),
+            content: (1..=20)
+                .map(|i| format!("{:4} +let x = {};", i, i))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            start_line: 1,
+            end_line: 21,
+            old_start_line: 1,
+            hunk: None,
+        };
+
+        assert_eq!(patch.split("gpt-4o", 1000).len(), 1);
+
+        let windows = patch.split("gpt-4o", 50);
+        assert!(windows.len() > 1);
+        assert_eq!(windows[0].start_line, 1);
+        assert_eq!(windows.last().unwrap().end_line, 21);
+        for pair in windows.windows(2) {
+            assert_eq!(
+                pair[0].end_line - pair[1].start_line,
+                WINDOW_OVERLAP_LINES as u64
+            );
+        }
+    }
+}
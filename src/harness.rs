@@ -0,0 +1,294 @@
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+
+use crate::core::{
+    Indication, IndicationKind, Indications, Linter, LlmClient, PatchReader, Patches, Rule, Rules,
+};
+use crate::patches::file::File;
+use crate::reporter::stdout::StdoutReporter;
+
+/// Fixtures of `rules.md` live in `rules.tests/<rule name>/{positive,negative}/*.patch`.
+pub fn fixtures_dir_for(rules_markdown_path: &str) -> PathBuf {
+    Path::new(rules_markdown_path).with_extension("tests")
+}
+
+/// Runs every fixture against the rule it belongs to and prints the results.
+/// Returns `true` if all cases passed.
+pub async fn run<L: LlmClient>(linter: &Linter<L>, fixtures_dir: &Path) -> Result<bool> {
+    let cases = collect(fixtures_dir, linter.rules())?;
+    let mut failed = 0;
+    let mut reported = Indications { values: Vec::new() };
+
+    for case in &cases {
+        let rule = linter
+            .rules()
+            .all
+            .iter()
+            .find(|rule| rule.name == case.rule)
+            .unwrap();
+        let patches = File::new(case.patch_path.to_string_lossy().to_string())
+            .read()
+            .await?;
+        case.validate(&patches, rule)?;
+
+        // Rules lowered to another severity are expected to be reported at that severity.
+        let expected_kind = rule.severity.map_or(IndicationKind::Error, |severity| {
+            severity.apply(IndicationKind::Error)
+        });
+        let indications = linter
+            .lint_with(
+                &Rules {
+                    all: vec![rule.clone()],
+                },
+                patches.clone(),
+            )
+            .await
+            .at_least(expected_kind);
+
+        match case.evaluate(&indications.values) {
+            Ok(()) => println!("test {} ... ok", case.name()),
+            Err(reason) => {
+                failed += 1;
+                println!("test {} ... FAILED\n  {}\n", case.name(), reason);
+                StdoutReporter::new(patches).print_indications(&indications);
+            }
+        }
+        reported.values.extend(indications.values);
+    }
+
+    println!(
+        "\ntest result: {}. {} passed; {} failed",
+        if failed == 0 { "ok" } else { "FAILED" },
+        cases.len() - failed,
+        failed
+    );
+    println!("\n{}", linter.summary(&reported));
+    Ok(failed == 0)
+}
+
+fn collect(fixtures_dir: &Path, rules: &Rules) -> Result<Vec<Case>> {
+    let mut cases = Vec::new();
+
+    for rule_dir in sorted_entries(fixtures_dir)? {
+        let rule = rule_dir.file_name().unwrap().to_string_lossy().to_string();
+        if !rules.all.iter().any(|r| r.name == rule) {
+            return Err(anyhow!(
+                "Fixtures found for unknown rule: {} ({})",
+                rule,
+                rule_dir.display()
+            ));
+        }
+
+        for (kind, positive) in [("positive", true), ("negative", false)] {
+            let dir = rule_dir.join(kind);
+            if !dir.is_dir() {
+                continue;
+            }
+            for patch_path in sorted_entries(&dir)? {
+                if patch_path.extension().is_some_and(|e| e == "patch") {
+                    cases.push(Case::load(rule.clone(), patch_path, positive)?);
+                }
+            }
+        }
+    }
+
+    for rule in &rules.all {
+        if !cases.iter().any(|case| case.rule == rule.name) {
+            println!("note: rule {} has no fixtures", rule.name);
+        }
+    }
+
+    Ok(cases)
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut entries = read_dir(dir)
+        .with_context(|| format!("Could not read directory: {}", dir.display()))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<PathBuf>, _>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+struct Case {
+    rule: String,
+    patch_path: PathBuf,
+    expectation: Expectation,
+}
+
+enum Expectation {
+    /// At least one indication must be reported. If locations are listed in the
+    /// `<case>.json` next to the patch, each of them must be covered and nothing else reported.
+    Positive(Vec<ExpectedLocation>),
+    /// No indication must be reported.
+    Negative,
+}
+
+#[derive(Deserialize, Debug)]
+struct ExpectedLocation {
+    path: String,
+    start_line: u64,
+    end_line: u64,
+}
+
+impl ExpectedLocation {
+    fn is_covered_by(&self, indication: &Indication) -> bool {
+        indication.location.path == self.path
+            && indication.location.start_line <= self.end_line
+            && self.start_line <= indication.location.end_line
+    }
+}
+
+impl Case {
+    fn load(rule: String, patch_path: PathBuf, positive: bool) -> Result<Case> {
+        let expected_path = patch_path.with_extension("json");
+        let expectation = if !positive {
+            Expectation::Negative
+        } else if expected_path.is_file() {
+            let raw = read_to_string(&expected_path)?;
+            let expected = serde_json::from_str(&raw)
+                .with_context(|| format!("Invalid expectation: {}", expected_path.display()))?;
+            Expectation::Positive(expected)
+        } else {
+            Expectation::Positive(Vec::new())
+        };
+
+        Ok(Case {
+            rule,
+            patch_path,
+            expectation,
+        })
+    }
+
+    fn name(&self) -> String {
+        let kind = match self.expectation {
+            Expectation::Positive(_) => "positive",
+            Expectation::Negative => "negative",
+        };
+        format!(
+            "{}/{}/{}",
+            self.rule,
+            kind,
+            self.patch_path.file_stem().unwrap().to_string_lossy()
+        )
+    }
+
+    /// Catches broken fixtures, which would otherwise show up as confusing failures.
+    fn validate(&self, patches: &Patches, rule: &Rule) -> Result<()> {
+        if patches.all.is_empty() {
+            return Err(anyhow!("{}: fixture has no hunks", self.name()));
+        }
+        if let Some(patch) = patches.all.iter().find(|p| !rule.matches(&p.path)) {
+            return Err(anyhow!(
+                "{}: {} does not match the glob of rule {}",
+                self.name(),
+                patch.path,
+                rule.name
+            ));
+        }
+        if let Expectation::Positive(expected) = &self.expectation {
+            for location in expected {
+                let in_hunk = patches.all.iter().any(|patch| {
+                    patch.path == location.path
+                        && patch.start_line <= location.start_line
+                        && location.end_line <= patch.end_line
+                });
+                if !in_hunk {
+                    return Err(anyhow!(
+                        "{}: expected location {:?} is outside of the fixture hunks",
+                        self.name(),
+                        location
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self, reported: &[Indication]) -> Result<(), String> {
+        match &self.expectation {
+            Expectation::Negative => {
+                if reported.is_empty() {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "expected no indication, but {} were reported",
+                        reported.len()
+                    ))
+                }
+            }
+            Expectation::Positive(expected) if expected.is_empty() => {
+                if reported.is_empty() {
+                    Err("expected an indication, but none were reported".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+            Expectation::Positive(expected) => {
+                if let Some(missing) = expected
+                    .iter()
+                    .find(|e| !reported.iter().any(|i| e.is_covered_by(i)))
+                {
+                    return Err(format!(
+                        "expected an indication at {}:{}-{}, but it was not reported",
+                        missing.path, missing.start_line, missing.end_line
+                    ));
+                }
+                if let Some(unexpected) = reported
+                    .iter()
+                    .find(|i| !expected.iter().any(|e| e.is_covered_by(i)))
+                {
+                    return Err(format!(
+                        "unexpected indication at {}:{}-{}",
+                        unexpected.location.path,
+                        unexpected.location.start_line,
+                        unexpected.location.end_line
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indication(start_line: u64, end_line: u64) -> Indication {
+        Indication::for_test("src/main.rs", start_line, end_line)
+    }
+
+    fn case(expectation: Expectation) -> Case {
+        Case {
+            rule: "rust".to_string(),
+            patch_path: PathBuf::from("rules.tests/rust/positive/unwrap.patch"),
+            expectation,
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let negative = case(Expectation::Negative);
+        assert!(negative.evaluate(&[]).is_ok());
+        assert!(negative.evaluate(&[indication(1, 1)]).is_err());
+
+        let positive = case(Expectation::Positive(Vec::new()));
+        assert!(positive.evaluate(&[]).is_err());
+        assert!(positive.evaluate(&[indication(1, 1)]).is_ok());
+
+        let located = case(Expectation::Positive(vec![ExpectedLocation {
+            path: "src/main.rs".to_string(),
+            start_line: 10,
+            end_line: 12,
+        }]));
+        assert!(located.evaluate(&[indication(12, 14)]).is_ok());
+        assert!(located.evaluate(&[indication(1, 1)]).is_err());
+        assert!(located
+            .evaluate(&[indication(12, 14), indication(20, 20)])
+            .is_err());
+    }
+}
@@ -1,56 +1,83 @@
-use crate::core::{Indication, IndicationKind, LlmClient, Location};
+use crate::core::{Completion, Indication, IndicationKind, LlmClient, Location, Reasoning, Usage};
 use anyhow::anyhow;
 use anyhow::Result;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use openai::set_base_url;
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json;
+use std::time::Duration;
+use tokio::time::sleep;
 
 pub struct OpenAI {
     model: String,
     api_key: String,
 }
 
+/// Retries of a request on rate limits and server errors, with exponential backoff.
+const MAX_RETRIES: u32 = 3;
+
 impl LlmClient for OpenAI {
-    fn new(api_key: String, model: String) -> OpenAI {
-        OpenAI { model, api_key }
+    fn model(&self) -> &str {
+        &self.model
     }
 
-    async fn check(&self, path: String, prompt: String) -> Result<Vec<Indication>> {
+    async fn check(&self, prompt: String) -> Result<Completion> {
         set_base_url("https://api.openai.com/v1/".to_string());
 
-        let indications = serde_json::from_str::<ResponseContent>(
-            &self.request_chat(prompt).await.unwrap_or_else(|e| {
-                panic!("Failed to get response: {}", e);
-            }),
-        )
-        .unwrap_or_else(|e| {
+        let (content, usage, retries) = self.request_chat(prompt).await.unwrap_or_else(|e| {
+            panic!("Failed to get response: {}", e);
+        });
+        let indications = serde_json::from_str::<ResponseContent>(&content).unwrap_or_else(|e| {
             panic!("Failed to parse response: {}", e);
         });
 
-        Ok(indications
+        let indications = indications
             .messages
             .iter()
             .map(move |indication| Indication {
                 kind: match indication.kind.as_str() {
                     "error" => IndicationKind::Error,
                     "warning" => IndicationKind::Warning,
+                    "info" => IndicationKind::Info,
+                    "hint" => IndicationKind::Hint,
                     "cancel" => IndicationKind::Cancel,
                     _ => panic!("Unknown indication kind: {}", indication.kind),
                 },
                 message: indication.message.clone(),
                 location: Location {
-                    path: path.clone(),
+                    path: indication.location.path.clone(),
                     start_line: indication.location.start_line,
                     end_line: indication.location.end_line,
                 },
+                rule: indication.rule.clone(),
+                suggestion: indication.suggestion.clone(),
+                reasoning: Some(Reasoning {
+                    quotation: indication.quotation.clone(),
+                    reference: indication.reference.clone(),
+                    suspicious_reason: indication.suspicious_reason.clone(),
+                    no_problem_reason: indication.no_problem_reason.clone(),
+                    reevaluation: indication.reevaluation.clone(),
+                }),
+                fingerprint: None,
             })
-            .collect())
+            .collect();
+
+        Ok(Completion {
+            indications,
+            usage,
+            retries,
+        })
     }
 }
 
 impl OpenAI {
-    async fn request_chat(&self, message: String) -> Result<String> {
+    pub fn new(api_key: String, model: String) -> OpenAI {
+        OpenAI { model, api_key }
+    }
+
+    /// Returns the content of the response, its usage and the number of retries.
+    async fn request_chat(&self, message: String) -> Result<(String, Usage, u32)> {
         let client = reqwest::Client::new();
         let request_json = ChatRequest {
             model: self.model.clone(),
@@ -68,32 +95,59 @@ impl OpenAI {
             Err(e) => panic!("Failed to serialize request: {}", e),
         };
 
-        let response = client
-            .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", &format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .body(messages_json)
-            .send()
-            .await?;
+        let mut retries = 0;
+        let (status, text) = loop {
+            let result = client
+                .post("https://api.openai.com/v1/chat/completions")
+                .header("Authorization", &format!("Bearer {}", self.api_key))
+                .header("Content-Type", "application/json")
+                .body(messages_json.clone())
+                .send()
+                .await;
+            let retryable = match &result {
+                Ok(response) => {
+                    response.status() == StatusCode::TOO_MANY_REQUESTS
+                        || response.status().is_server_error()
+                }
+                Err(_) => true,
+            };
+            if !retryable || retries == MAX_RETRIES {
+                let response = result?;
+                break (response.status(), response.text().await?);
+            }
+
+            retries += 1;
+            let backoff = Duration::from_secs(2u64.pow(retries));
+            warn!(
+                "OpenAI API request failed ({}), retrying in {:?}",
+                result.map_or_else(|e| e.to_string(), |r| r.status().to_string()),
+                backoff
+            );
+            sleep(backoff).await;
+        };
 
-        let status = response.status();
-        let text = response.text().await?;
         if !status.is_success() {
             error!("OpenAI API Error: status: {}, response: {}", status, &text);
             error!("Request: {}", message);
-            anyhow!("Failed to get response: {}", status);
+            return Err(anyhow!("Failed to get response: {}", status));
         }
 
         let response = serde_json::from_str::<ApiResponse>(&text)?;
 
         info!("Total tokens: {}", response.usage.total_tokens);
-        info!("Cost: {:.2} JPY", response.usage.cost_as_jpy());
 
         let indication = response.choices.first().unwrap().message.content.clone();
 
         debug!("OpenAI Response: {}\n====\n{}", message, indication);
 
-        Ok(indication)
+        Ok((
+            indication,
+            Usage {
+                prompt_tokens: response.usage.prompt_tokens,
+                completion_tokens: response.usage.completion_tokens,
+            },
+            retries,
+        ))
     }
 }
 
@@ -113,7 +167,7 @@ struct ResponseFormat {
 #[derive(Deserialize, Debug)]
 struct ApiResponse {
     choices: Vec<Choice>,
-    usage: Usage,
+    usage: ApiUsage,
 }
 
 #[derive(Deserialize, Debug)]
@@ -133,21 +187,10 @@ struct ResponseContent {
 }
 
 #[derive(Deserialize, Debug)]
-struct Usage {
-    prompt_tokens: u32,
-    completion_tokens: u32,
-    total_tokens: u32,
-}
-
-// Ref: https://openai.com/api/pricing/
-const INPUT_TOKEN_UNIT_PRICE: f64 = 5.0 / 1000.0 / 150.0; // $5 per 1000 tokens, $1 per 150 JPY.
-const OUTPUT_TOKEN_UNIT_PRICE: f64 = 15.0 / 1000.0 / 150.0; // $15 per 1000 tokens, $1 per 150 JPY.
-
-impl Usage {
-    fn cost_as_jpy(&self) -> f64 {
-        (self.prompt_tokens as f64 * INPUT_TOKEN_UNIT_PRICE)
-            + (self.completion_tokens as f64 * OUTPUT_TOKEN_UNIT_PRICE)
-    }
+struct ApiUsage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    total_tokens: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -155,10 +198,27 @@ struct GptIndication {
     pub kind: String,
     pub message: String,
     pub location: GptIndicationLocation,
+    #[serde(default)]
+    pub rule: Option<String>,
+    #[serde(default)]
+    pub suggestion: Option<String>,
+    #[serde(default)]
+    pub quotation: String,
+    #[serde(default)]
+    pub reference: String,
+    #[serde(default, rename = "suspiciousReason")]
+    pub suspicious_reason: String,
+    #[serde(default, rename = "noProblemReason")]
+    pub no_problem_reason: String,
+    // The prompt asks for this misspelled key.
+    #[serde(default, rename = "reevalution")]
+    pub reevaluation: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 struct GptIndicationLocation {
+    #[serde(default)]
+    pub path: String,
     pub start_line: u64,
     pub end_line: u64,
 }
@@ -0,0 +1,188 @@
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::{Completion, Indication, LlmClient, Usage};
+
+/// Forwards requests to the inner client and records each prompt and its response
+/// into the fixture directory, so that they can be served by `Replayer` later.
+pub struct Recorder<L: LlmClient> {
+    inner: L,
+    dir: PathBuf,
+}
+
+impl<L: LlmClient> Recorder<L> {
+    pub fn new(inner: L, dir: PathBuf) -> Recorder<L> {
+        Recorder { inner, dir }
+    }
+}
+
+impl<L: LlmClient> LlmClient for Recorder<L> {
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    async fn check(&self, prompt: String) -> Result<Completion> {
+        let completion = self.inner.check(prompt.clone()).await?;
+
+        let recording = Recording {
+            model: self.model().to_string(),
+            prompt,
+            indications: completion.indications,
+            usage: completion.usage,
+        };
+        let fixture_path = fixture_path(&self.dir, &recording.model, &recording.prompt);
+        create_dir_all(&self.dir)
+            .with_context(|| format!("Could not create directory: {}", self.dir.display()))?;
+        write(&fixture_path, serde_json::to_string_pretty(&recording)?)
+            .with_context(|| format!("Could not write file: {}", fixture_path.display()))?;
+        debug!("Recorded response: {}", fixture_path.display());
+
+        Ok(Completion {
+            indications: recording.indications,
+            usage: recording.usage,
+            retries: completion.retries,
+        })
+    }
+}
+
+/// Serves responses recorded by `Recorder` without accessing the network.
+pub struct Replayer {
+    model: String,
+    dir: PathBuf,
+}
+
+impl Replayer {
+    pub fn new(model: String, dir: PathBuf) -> Replayer {
+        Replayer { model, dir }
+    }
+}
+
+impl LlmClient for Replayer {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn check(&self, prompt: String) -> Result<Completion> {
+        let fixture_path = fixture_path(&self.dir, &self.model, &prompt);
+        if !fixture_path.is_file() {
+            return Err(anyhow!(
+                "No recorded response for the prompt: {}",
+                fixture_path.display()
+            ));
+        }
+        let recording = serde_json::from_str::<Recording>(&read_to_string(&fixture_path)?)
+            .with_context(|| format!("Invalid recording: {}", fixture_path.display()))?;
+        debug!("Replayed response: {}", fixture_path.display());
+
+        Ok(Completion {
+            indications: recording.indications,
+            usage: recording.usage,
+            retries: 0,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Recording {
+    model: String,
+    prompt: String,
+    indications: Vec<Indication>,
+    #[serde(default)]
+    usage: Usage,
+}
+
+fn fixture_path(dir: &Path, model: &str, prompt: &str) -> PathBuf {
+    let hash = Sha256::new()
+        .chain_update(model)
+        .chain_update([0])
+        .chain_update(prompt)
+        .finalize();
+    dir.join(format!("{:x}.json", hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::remove_dir_all;
+    use std::process;
+    use std::time::Duration;
+
+    use indoc::indoc;
+
+    use super::*;
+    use crate::core::{Linter, Patches, Rule, Rules};
+
+    struct Stub {}
+
+    impl LlmClient for Stub {
+        fn model(&self) -> &str {
+            "stub"
+        }
+
+        async fn check(&self, _prompt: String) -> Result<Completion> {
+            let indications = vec![Indication {
+                message: "Do not use unwrap".to_string(),
+                ..Indication::for_test("src/main.rs", 2, 2)
+            }];
+            Ok(Completion {
+                indications,
+                usage: Usage::default(),
+                retries: 0,
+            })
+        }
+    }
+
+    fn rules() -> Rules {
+        Rules {
+            all: vec![Rule {
+                name: "rust".to_string(),
+                target_file_glob: "src/**/*.rs".to_string(),
+                content: "- Do not use `unwrap`".to_string(),
+                severity: None,
+            }],
+        }
+    }
+
+    fn patches() -> Patches {
+        Patches::parse(indoc! {"
+            --- a/src/main.rs
+            +++ b/src/main.rs
+            @@ -1,2 +1,3 @@
+             fn main() {
+            +    run().unwrap();
+             }
+        "})
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let dir = temp_dir().join(format!("llm-linter-replay-{}", process::id()));
+
+        let recorded = Linter::new(Recorder::new(Stub {}, dir.clone()), rules())
+            .with_interval(Duration::ZERO)
+            .lint(patches())
+            .await;
+        let replayed = Linter::new(Replayer::new("stub".to_string(), dir.clone()), rules())
+            .with_interval(Duration::ZERO)
+            .lint(patches())
+            .await;
+        let unrecorded = Replayer::new("other".to_string(), dir.clone())
+            .check("prompt".to_string())
+            .await;
+        remove_dir_all(&dir).unwrap();
+
+        assert_eq!(replayed.values.len(), 1);
+        assert_eq!(replayed.values[0].location.path, "src/main.rs");
+        assert_eq!(
+            serde_json::to_string(&recorded.values).unwrap(),
+            serde_json::to_string(&replayed.values).unwrap()
+        );
+        assert!(unrecorded.is_err());
+    }
+}
@@ -1,90 +1,728 @@
-use core::{LlmClient, PatchReader, Reporter};
-use std::env;
+use core::{IndicationKind, LlmClient, PatchReader, Reporter};
+use log::{info, warn};
+use reporter::fan_out::{AnyReporter, FanOut, ReporterSpec};
+use std::path::PathBuf;
+use std::time::Duration;
+use std::{env, panic, process};
 
+mod action;
+mod baseline;
+mod cache;
+mod context;
 mod core;
+mod harness;
 mod llm_clients;
 mod patches;
+mod pricing;
 mod reporter;
 mod rules;
+mod summary;
+mod suppressions;
+mod tokens;
+
+const DEFAULT_BASELINE: &str = "llm-lint-baseline.json";
+
+/// Exit code when indications at least as severe as `--fail-on` are found.
+const EXIT_FINDINGS: i32 = 1;
+/// Exit code when the linter itself failed, e.g. on a bad option or an API error.
+const EXIT_FAILURE: i32 = 2;
 
 #[tokio::main]
 async fn main() {
     env_logger::init();
+    // Failures panic, which would exit with 101, so that CI tells them from findings.
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        process::exit(EXIT_FAILURE);
+    }));
 
-    let args: Vec<String> = env::args().collect();
+    let args = action::args_with_inputs(env::args().collect());
     let mut opts = getopts::Options::new();
     opts.optopt("r", "rules", "Path to the rules markdown file", "RULES");
-    opts.optopt("o", "owner", "Owner of the repository", "OWNER");
-    opts.optopt("p", "repository", "Repository name", "REPOSITORY");
-    opts.optopt("n", "pull", "Pull request number", "PULL_NUMBER");
+    opts.optopt(
+        "o",
+        "owner",
+        "Owner of the repository (default: from the GitHub Actions event)",
+        "OWNER",
+    );
+    opts.optopt(
+        "p",
+        "repository",
+        "Repository name (default: from the GitHub Actions event)",
+        "REPOSITORY",
+    );
+    opts.optopt(
+        "n",
+        "pull",
+        "Pull request number (default: from the GitHub Actions event)",
+        "PULL_NUMBER",
+    );
+    opts.optopt(
+        "",
+        "platform",
+        "Where the pull request is hosted: github, gitlab or gitea (default: detected from the CI, or github)",
+        "PLATFORM",
+    );
+    opts.optopt(
+        "",
+        "server-url",
+        "URL of the GitLab or Gitea instance (default: detected from the CI, or https://gitlab.com)",
+        "URL",
+    );
+    opts.optopt(
+        "",
+        "diff",
+        "Lint a unified diff file instead of a pull request",
+        "DIFF",
+    );
+    opts.optmulti(
+        "",
+        "reporter",
+        "Where to report, repeatable: github (review comments), gitlab (merge request discussions), gitea (review), gerrit (review input JSON), rdjson or rdjsonl (reviewdog), checkstyle, junit, html, actions (annotations and job summary) or stdout, optionally followed by :error, :warning, :info, :hint or :cancel for the least severe indications reported and =FILE for the file written (default: the platform, or stdout with --diff)",
+        "NAME[:SEVERITY][=FILE]",
+    );
+    opts.optopt(
+        "",
+        "min-severity",
+        "Least severe indications reported: error, warning, info, hint or cancel (default: per reporter, error for comments on the pull request)",
+        "SEVERITY",
+    );
+    opts.optopt(
+        "",
+        "fail-on",
+        "Exit with 1 if indications at least this severe are found: error, warning, info, hint, cancel or never (default: never)",
+        "SEVERITY",
+    );
+    opts.optopt(
+        "",
+        "output",
+        "File the gerrit, rdjson, rdjsonl, checkstyle, junit and html reporters write to unless given with =FILE (default: stdout)",
+        "FILE",
+    );
+    opts.optflag(
+        "",
+        "incremental",
+        "Only lint hunks changed since the last run on the pull request",
+    );
+    opts.optopt(
+        "",
+        "fixtures",
+        "Directory of rule test fixtures (default: <rules>.tests)",
+        "FIXTURES",
+    );
+    opts.optopt(
+        "",
+        "max-tokens",
+        "Estimated tokens of hunks to lint at most; the rest is skipped (default: 100000)",
+        "TOKENS",
+    );
+    opts.optopt(
+        "",
+        "max-hunk-tokens",
+        "Split hunks larger than the estimated tokens into overlapping windows (default: 4000)",
+        "TOKENS",
+    );
+    opts.optopt(
+        "",
+        "budget",
+        "Maximum cost of a run, in the currency of the pricing (default: USD)",
+        "AMOUNT",
+    );
+    opts.optopt(
+        "",
+        "pr-budget",
+        "Maximum cost of all runs on the pull request, in the currency of the pricing",
+        "AMOUNT",
+    );
+    opts.optopt(
+        "",
+        "pricing",
+        "JSON file of model prices per 1M tokens, replacing the built-in table",
+        "PRICING",
+    );
+    opts.optopt(
+        "",
+        "batch-tokens",
+        "Send hunks matching the same rules together, up to the estimated tokens (default: 0, one request per hunk)",
+        "TOKENS",
+    );
+    opts.optopt(
+        "",
+        "cache",
+        "Cache LLM responses in the directory and reuse them for unchanged hunks",
+        "DIR",
+    );
+    opts.optopt(
+        "",
+        "samples",
+        "Ask the model this many times about each hunk and keep the indications the quorum agrees on (default: 1)",
+        "N",
+    );
+    opts.optmulti(
+        "",
+        "vote-model",
+        "Also ask these models about each hunk, repeatable or separated by commas, and keep the indications the quorum agrees on",
+        "MODEL",
+    );
+    opts.optopt(
+        "",
+        "quorum",
+        "Answers that must agree on an indication, by overlapping lines and rule, to keep it (default: a majority of the samples of all models)",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "baseline",
+        "Do not report the indications in the baseline file, which the baseline command writes (default for the baseline command: llm-lint-baseline.json)",
+        "FILE",
+    );
+    opts.optopt(
+        "",
+        "record",
+        "Record LLM responses into the directory",
+        "DIR",
+    );
+    opts.optopt(
+        "",
+        "replay",
+        "Replay LLM responses recorded in the directory instead of calling the API",
+        "DIR",
+    );
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => m,
         Err(f) => {
             panic!("{}", f.to_string())
         }
     };
+    let rules_markdown_path = matches
+        .opt_str("rules")
+        .unwrap_or_else(|| panic!("--rules must be set"));
+    let rules = rules::markdown::read(&rules_markdown_path);
+    let model = action::input("model")
+        .or_else(|| env::var("OPENAI_MODEL").ok())
+        .unwrap_or_else(|| "gpt-4o".to_string());
+    let openai = |model: &str| {
+        llm_clients::openai::OpenAI::new(
+            env_or_input("OPENAI_API_KEY", "openai_api_key"),
+            model.to_string(),
+        )
+    };
+
+    let vote_models = matches
+        .opt_strs("vote-model")
+        .iter()
+        .flat_map(|models| models.split(','))
+        .map(|model| model.trim().to_string())
+        .filter(|model| !model.is_empty())
+        .collect::<Vec<String>>();
+    let samples: usize = matches
+        .opt_str("samples")
+        .map(|s| {
+            s.parse()
+                .unwrap_or_else(|_| panic!("Failed to parse samples"))
+        })
+        .unwrap_or(1);
+    let answers = (1 + vote_models.len()) * samples;
+    let quorum = matches
+        .opt_str("quorum")
+        .map(|s| {
+            s.parse()
+                .unwrap_or_else(|_| panic!("Failed to parse quorum"))
+        })
+        .unwrap_or(answers / 2 + 1);
+    if samples == 0 || quorum == 0 || quorum > answers {
+        panic!(
+            "The quorum must be between 1 and the {} answers of each hunk",
+            answers
+        );
+    }
+
+    // A recording is looked up by its model and prompt, which all samples share.
+    if samples > 1 && (matches.opt_present("record") || matches.opt_present("replay")) {
+        panic!("--samples cannot be more than 1 with --record or --replay");
+    }
+
+    match (matches.opt_str("record"), matches.opt_str("replay")) {
+        (None, None) => {
+            let others = vote_models.iter().map(|model| openai(model)).collect();
+            run(
+                core::Linter::new(openai(&model), rules).with_voting(others, samples, quorum),
+                matches,
+                rules_markdown_path,
+            )
+            .await
+        }
+        (Some(dir), None) => {
+            let recorder = |model: &str| {
+                llm_clients::replay::Recorder::new(openai(model), PathBuf::from(&dir))
+            };
+            let others = vote_models.iter().map(|model| recorder(model)).collect();
+            run(
+                core::Linter::new(recorder(&model), rules).with_voting(others, samples, quorum),
+                matches,
+                rules_markdown_path,
+            )
+            .await
+        }
+        (None, Some(dir)) => {
+            let replayer = |model: &str| {
+                llm_clients::replay::Replayer::new(model.to_string(), PathBuf::from(&dir))
+            };
+            let others = vote_models.iter().map(|model| replayer(model)).collect();
+            run(
+                core::Linter::new(replayer(&model), rules)
+                    .with_voting(others, samples, quorum)
+                    .with_interval(Duration::ZERO),
+                matches,
+                rules_markdown_path,
+            )
+            .await
+        }
+        (Some(_), Some(_)) => panic!("--record and --replay cannot be used together"),
+    }
+}
+
+async fn run<L: LlmClient>(
+    linter: core::Linter<L>,
+    matches: getopts::Matches,
+    rules_markdown_path: String,
+) {
+    let linter = match matches.opt_str("cache") {
+        Some(dir) => linter.with_cache(cache::Cache::new(PathBuf::from(dir))),
+        None => linter,
+    };
+    let linter = match matches.opt_str("pricing") {
+        Some(path) => linter.with_pricing(
+            pricing::Pricing::read(&path)
+                .unwrap_or_else(|e| panic!("Failed to read pricing: {}", e)),
+        ),
+        None => linter,
+    };
+    let linter = match matches.opt_str("batch-tokens") {
+        Some(tokens) => linter.with_batch_tokens(
+            tokens
+                .parse()
+                .unwrap_or_else(|_| panic!("Failed to parse batch tokens")),
+        ),
+        None => linter,
+    };
+
+    match matches.free.first().map(String::as_str) {
+        None | Some("lint") | Some("baseline") => {}
+        Some("test") => {
+            let fixtures_dir = matches
+                .opt_str("fixtures")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| harness::fixtures_dir_for(&rules_markdown_path));
+            let passed = harness::run(&linter, &fixtures_dir)
+                .await
+                .unwrap_or_else(|e| panic!("Failed to run rule tests: {}", e));
+            if !passed {
+                process::exit(EXIT_FINDINGS);
+            }
+            return;
+        }
+        Some(command) => panic!("Unknown command: {}", command),
+    }
+
+    let platform = matches.opt_str("platform").unwrap_or_else(|| {
+        if env::var("GITLAB_CI").is_ok() {
+            "gitlab".to_string()
+        } else if env::var("GITEA_ACTIONS").is_ok() {
+            "gitea".to_string()
+        } else {
+            "github".to_string()
+        }
+    });
+
+    // A diff file is linted on its own, without any pull request.
+    let pull_request = match matches.opt_str("diff") {
+        Some(_) => None,
+        None => Some(pull_request(&matches, &platform)),
+    };
+
+    let mut reporters = matches.opt_strs("reporter");
+    if reporters.is_empty() {
+        reporters.push(if pull_request.is_some() {
+            platform.clone()
+        } else {
+            "stdout".to_string()
+        });
+    }
+    let min_kind = matches
+        .opt_str("min-severity")
+        .map(|name| IndicationKind::parse(&name).unwrap_or_else(|e| panic!("{}", e)));
+    let reporters = reporters
+        .iter()
+        .map(|spec| {
+            let spec = ReporterSpec::parse(spec, min_kind).unwrap_or_else(|e| panic!("{}", e));
+            match spec.name.as_str() {
+                "github" | "gitlab" | "gitea" if pull_request.is_none() => {
+                    panic!("The {} reporter cannot be used with --diff", spec.name)
+                }
+                "github" | "gitlab" | "gitea" if spec.name != platform => {
+                    panic!("The {} reporter cannot be used on {}", spec.name, platform)
+                }
+                _ => spec,
+            }
+        })
+        .collect();
+
     let config = Config {
-        rules_markdown_path: matches
-            .opt_str("rules")
-            .unwrap_or_else(|| panic!("--rules must be set")),
-        owner: matches
-            .opt_str("owner")
-            .unwrap_or_else(|| panic!("--owner must be set")),
-        repository: matches
-            .opt_str("repository")
-            .unwrap_or_else(|| panic!("--repository must be set")),
-        pull_number: matches
-            .opt_str("pull")
+        platform,
+        server_url: matches.opt_str("server-url"),
+        pull_request,
+        diff: matches.opt_str("diff"),
+        output: matches.opt_str("output").map(PathBuf::from),
+        incremental: matches.opt_present("incremental"),
+        max_tokens: matches
+            .opt_str("max-tokens")
+            .map(|s| {
+                s.parse()
+                    .unwrap_or_else(|_| panic!("Failed to parse max tokens"))
+            })
+            .unwrap_or(100_000),
+        max_hunk_tokens: matches
+            .opt_str("max-hunk-tokens")
             .map(|s| {
                 s.parse()
-                    .unwrap_or_else(|_| panic!("Failed to parse pull number"))
+                    .unwrap_or_else(|_| panic!("Failed to parse max hunk tokens"))
             })
-            .unwrap_or_else(|| panic!("--pull must be set")),
+            .unwrap_or(4_000),
+        reporters,
+        fail_on: match matches.opt_str("fail-on").as_deref() {
+            None | Some("never") => None,
+            Some(name) => Some(IndicationKind::parse(name).unwrap_or_else(|e| panic!("{}", e))),
+        },
+        budget: matches.opt_str("budget").map(|s| {
+            s.parse()
+                .unwrap_or_else(|_| panic!("Failed to parse budget"))
+        }),
+        pr_budget: matches.opt_str("pr-budget").map(|s| {
+            s.parse()
+                .unwrap_or_else(|_| panic!("Failed to parse PR budget"))
+        }),
     };
+    reporter::fan_out::check_destinations(&config.reporters, config.output.as_deref())
+        .unwrap_or_else(|e| panic!("{}", e));
 
-    let rules = rules::markdown::read(&config.rules_markdown_path);
-    let llm_client = llm_clients::openai::OpenAI::new(
-        env::var("OPENAI_API_KEY").unwrap_or_else(|_| panic!("OPENAI_API_KEY must be set")),
-        env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o".to_string()),
-    );
-    let linter = core::Linter::new(llm_client, rules);
-    let github_patches_client = patches::github::Github::new(
-        secrecy::Secret::new(
-            env::var("GITHUB_TOKEN").unwrap_or_else(|_| panic!("GITHUB_TOKEN must be set")),
+    // The state of previous runs is only kept on GitHub pull requests yet.
+    let keeps_state = config.pull_request.is_some() && config.platform == "github";
+    if !keeps_state && (config.incremental || config.pr_budget.is_some()) {
+        panic!("--incremental and --pr-budget are only supported on GitHub pull requests");
+    }
+    let github_patches_client = keeps_state.then(|| {
+        let pull_request = config.pull_request();
+        let client = patches::github::Github::new(
+            secrecy::Secret::new(env_or_input("GITHUB_TOKEN", "github_token")),
+            pull_request.owner.clone(),
+            pull_request.repository.clone(),
+            pull_request.number,
+        );
+        if config.incremental {
+            client.incremental()
+        } else {
+            client
+        }
+    });
+    let spent_on_pull = match (&github_patches_client, config.pr_budget) {
+        (Some(client), Some(_)) => client
+            .spent()
+            .await
+            .unwrap_or_else(|e| panic!("Failed to read the cost spent on the pull request: {}", e)),
+        _ => 0.0,
+    };
+    let budget = [
+        config.budget,
+        config.pr_budget.map(|budget| budget - spent_on_pull),
+    ]
+    .into_iter()
+    .flatten()
+    .reduce(f64::min);
+    if let Some(model) = budget.and(linter.unpriced_model()) {
+        panic!(
+            "No price for model {} to enforce the budget, give one with --pricing",
+            model
+        );
+    }
+    let linter = match budget {
+        Some(budget) => linter.with_budget(budget.max(0.0)),
+        None => linter,
+    };
+
+    // Comments go on the commit the patches were read at, for their lines to match even
+    // if the pull request has been pushed to since the event.
+    let (patches, read_sha) = match (
+        &config.diff,
+        &github_patches_client,
+        config.platform.as_str(),
+    ) {
+        (Some(path), _, _) => (patches::file::File::new(path.clone()).read().await, None),
+        (None, Some(client), _) => (client.read().await, client.read_sha()),
+        (None, None, "gitlab") => (
+            patches::gitlab::Gitlab::new(gitlab_client(&config), config.pull_request().number)
+                .read()
+                .await,
+            None,
         ),
-        config.owner.clone(),
-        config.repository.clone(),
-        config.pull_number,
-    );
-    let patches = github_patches_client.read().await.unwrap_or_else(|e| {
+        (None, None, _) => {
+            let client =
+                patches::gitea::Gitea::new(gitea_client(&config), config.pull_request().number);
+            (client.read().await, client.read_sha())
+        }
+    };
+    let patches = patches.unwrap_or_else(|e| {
         panic!("Failed to read patches: {}", e);
     });
+    let linter = linter.with_suppressions(suppressions::Suppressions::read(&patches));
+    // Hunks as read, before large ones are split into windows.
+    let hunks = patches.clone();
 
-    let max_total_patch_lines =
-        env::var("MAX_TOTAL_PATCH_LINES").unwrap_or_else(|_| "5000".to_string());
-    if patches.total_lines() > max_total_patch_lines.parse().unwrap() {
-        panic!(
-            "Total patch lines exceeds the limit: {}",
-            max_total_patch_lines
+    let plan = linter.plan(
+        patches,
+        &core::TokenBudget {
+            total: config.max_tokens,
+            hunk: config.max_hunk_tokens,
+        },
+    );
+    info!(
+        "Linting {} hunks, about {} tokens",
+        plan.linted.all.len(),
+        plan.tokens
+    );
+    for patch in &plan.skipped.all {
+        warn!(
+            "Skipped {} from line {} to {}: over the token budget",
+            patch.path, patch.start_line, patch.end_line
+        );
+    }
+    if !plan.skipped.all.is_empty() {
+        warn!(
+            "Skipped {} of {} hunks to stay within {} tokens",
+            plan.skipped.all.len(),
+            plan.skipped.all.len() + plan.linted.all.len(),
+            config.max_tokens
         );
     }
 
-    let indications = linter.lint(patches).await;
-    let reporter = reporter::github::GithubReporter::new(
+    let linted = plan.linted.clone();
+    let indications = linter.lint(plan.linted).await;
+
+    if matches.free.first().map(String::as_str) == Some("baseline") {
+        let path = matches
+            .opt_str("baseline")
+            .unwrap_or_else(|| DEFAULT_BASELINE.to_string());
+        let baseline = baseline::Baseline::new(&indications);
+        baseline
+            .write(&path)
+            .unwrap_or_else(|e| panic!("Failed to write the baseline: {}", e));
+        info!("Wrote {} indications to {}", baseline.len(), path);
+        return;
+    }
+    let indications = match matches.opt_str("baseline") {
+        Some(path) => {
+            let count = indications.values.len();
+            let indications = baseline::Baseline::read(&path)
+                .unwrap_or_else(|e| panic!("Failed to read the baseline: {}", e))
+                .filter(indications);
+            info!(
+                "Ignored {} indications in the baseline",
+                count - indications.values.len()
+            );
+            indications
+        }
+        None => indications,
+    };
+    let summary = linter.summary(&indications);
+    info!("Summary:\n{}", summary);
+    let mut fan_out = FanOut::new();
+    for spec in &config.reporters {
+        let output = spec.output.clone().or(config.output.clone());
+        let reporter = match spec.name.as_str() {
+            "github" => {
+                let pull_request = config.pull_request();
+                AnyReporter::Github(
+                    reporter::github::GithubReporter::new(
+                        secrecy::Secret::new(env_or_input("GITHUB_TOKEN", "github_token")),
+                        pull_request.owner.clone(),
+                        pull_request.repository.clone(),
+                        pull_request.number,
+                    )
+                    .with_commit_sha(read_sha.clone()),
+                )
+            }
+            "gitlab" => AnyReporter::Gitlab(reporter::gitlab::GitlabReporter::new(
+                gitlab_client(&config),
+                config.pull_request().number,
+                hunks.clone(),
+            )),
+            "gitea" => AnyReporter::Gitea(
+                reporter::gitea::GiteaReporter::new(
+                    gitea_client(&config),
+                    config.pull_request().number,
+                    hunks.clone(),
+                )
+                .with_commit_sha(read_sha.clone()),
+            ),
+            "gerrit" => {
+                AnyReporter::Gerrit(reporter::gerrit::GerritReporter::new(output, hunks.clone()))
+            }
+            "checkstyle" => {
+                AnyReporter::Checkstyle(reporter::checkstyle::CheckstyleReporter::new(output))
+            }
+            "junit" => AnyReporter::Junit(reporter::junit::JunitReporter::new(
+                output,
+                linter.rules(),
+                &linted,
+            )),
+            "html" => AnyReporter::Html(reporter::html::HtmlReporter::new(output, linted.clone())),
+            "rdjson" | "rdjsonl" => AnyReporter::Rdjson(reporter::rdjson::RdjsonReporter::new(
+                output,
+                spec.name == "rdjsonl",
+            )),
+            "actions" => AnyReporter::Actions(reporter::actions::ActionsReporter::new()),
+            "stdout" => AnyReporter::Stdout(reporter::stdout::StdoutReporter::new(linted.clone())),
+            _ => unreachable!(),
+        };
+        fan_out = fan_out.with_reporter(reporter, spec.min_kind);
+    }
+    // Failures are logged by the reporters, and the state is still saved for the cost spent.
+    let reported = fan_out.report(&indications, &summary).await;
+
+    if let Some(client) = github_patches_client {
+        if config.incremental || config.pr_budget.is_some() {
+            client
+                .save_state(
+                    linter.spent(),
+                    linter.currency(),
+                    summary.hunks_skipped == 0,
+                )
+                .await
+                .unwrap_or_else(|e| panic!("Failed to save the state of the run: {}", e));
+        }
+    }
+    if reported.is_err() {
+        process::exit(EXIT_FAILURE);
+    }
+
+    if let Some(kind) = config.fail_on {
+        let findings = indications.at_least(kind).values.len();
+        if findings > 0 {
+            warn!(
+                "Found {} indications at least as severe as {:?}",
+                findings, kind
+            );
+            process::exit(EXIT_FINDINGS);
+        }
+    }
+}
+
+/// Reads a secret from the environment variable, or from the input when run as an action.
+fn env_or_input(name: &str, input: &str) -> String {
+    env::var(name)
+        .ok()
+        .or_else(|| action::input(input))
+        .unwrap_or_else(|| panic!("{} must be set", name))
+}
+
+fn gitlab_client(config: &Config) -> patches::gitlab::Client {
+    patches::gitlab::Client::new(
+        config
+            .server_url
+            .clone()
+            .or_else(|| env::var("CI_SERVER_URL").ok())
+            .unwrap_or_else(|| "https://gitlab.com".to_string()),
         secrecy::Secret::new(
-            env::var("GITHUB_TOKEN").unwrap_or_else(|_| panic!("GITHUB_TOKEN must be set")),
+            env::var("GITLAB_TOKEN").unwrap_or_else(|_| panic!("GITLAB_TOKEN must be set")),
         ),
-        config.owner,
-        config.repository,
-        config.pull_number,
-    );
-    // let reporter = reporter::stdout::StdoutReporter::new();
-    reporter.report(indications).await;
+        format!(
+            "{}/{}",
+            config.pull_request().owner,
+            config.pull_request().repository
+        ),
+    )
+}
+
+fn gitea_client(config: &Config) -> patches::gitea::Client {
+    patches::gitea::Client::new(
+        config
+            .server_url
+            .clone()
+            .or_else(|| env::var("GITHUB_SERVER_URL").ok())
+            .unwrap_or_else(|| panic!("--server-url must be set")),
+        secrecy::Secret::new(
+            env::var("GITEA_TOKEN").unwrap_or_else(|_| panic!("GITEA_TOKEN must be set")),
+        ),
+        config.pull_request().owner.clone(),
+        config.pull_request().repository.clone(),
+    )
+}
+
+/// Resolves the pull request from the options, falling back to the one the CI run was
+/// triggered by.
+fn pull_request(matches: &getopts::Matches, platform: &str) -> PullRequest {
+    // Explicit options take precedence over the pull request that triggered the workflow.
+    let context = match platform {
+        // Gitea Actions provides the same variables and event payload as GitHub Actions.
+        "github" | "gitea" => context::from_actions_env()
+            .unwrap_or_else(|e| panic!("Failed to read the GitHub Actions event: {}", e)),
+        "gitlab" => context::from_gitlab_ci_env()
+            .unwrap_or_else(|e| panic!("Failed to read the GitLab CI variables: {}", e)),
+        _ => panic!("Unknown platform: {}", platform),
+    };
+    let owner = matches
+        .opt_str("owner")
+        .or_else(|| context.as_ref().map(|c| c.owner.clone()))
+        .unwrap_or_else(|| panic!("--owner must be set"));
+    let repository = matches
+        .opt_str("repository")
+        .or_else(|| context.as_ref().map(|c| c.repository.clone()))
+        .unwrap_or_else(|| panic!("--repository must be set"));
+    let number = matches
+        .opt_str("pull")
+        .map(|s| {
+            s.parse()
+                .unwrap_or_else(|_| panic!("Failed to parse pull number"))
+        })
+        .or_else(|| context.as_ref().map(|c| c.pull_number))
+        .unwrap_or_else(|| panic!("--pull must be set"));
+    PullRequest {
+        owner,
+        repository,
+        number,
+    }
 }
 
 struct Config {
-    rules_markdown_path: String,
+    platform: String,
+    /// URL of the GitLab or Gitea instance.
+    server_url: Option<String>,
+    /// `None` if `--diff` is given.
+    pull_request: Option<PullRequest>,
+    diff: Option<String>,
+    /// File the reporters writing a file, such as gerrit, write to.
+    output: Option<PathBuf>,
+    incremental: bool,
+    max_tokens: usize,
+    max_hunk_tokens: usize,
+    reporters: Vec<ReporterSpec>,
+    /// Least severe indications failing the run, or `None` to never fail on them.
+    fail_on: Option<IndicationKind>,
+    budget: Option<f64>,
+    pr_budget: Option<f64>,
+}
+
+impl Config {
+    fn pull_request(&self) -> &PullRequest {
+        self.pull_request
+            .as_ref()
+            .unwrap_or_else(|| panic!("A pull request is required without --diff"))
+    }
+}
+
+struct PullRequest {
     owner: String,
     repository: String,
-    pull_number: u64,
+    number: u64,
 }
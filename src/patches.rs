@@ -1,2 +1,4 @@
 pub mod file;
+pub mod gitea;
 pub mod github;
+pub mod gitlab;
@@ -1,22 +1,23 @@
 use std::fs::read_to_string;
 
-use crate::core::Patch;
+use anyhow::{Context, Result};
 
-pub fn from_file(path: &str) -> Patch {
-    let content = read_to_string(path).unwrap();
-    let re = regex::Regex::new(r"\+\+\+ b/(.*)").unwrap();
+use crate::core::{PatchReader, Patches};
 
-    Patch {
-        path: re
-            .captures(&content)
-            .unwrap_or_else(|| panic!("Path not found in patch file"))
-            .get(1)
-            .unwrap_or_else(|| panic!("Failed to extract path"))
-            .as_str()
-            .to_string(),
-        content,
-        // TODO
-        start_line: 0,
-        end_line: 0,
+pub struct File {
+    path: String,
+}
+
+impl File {
+    pub fn new(path: String) -> File {
+        File { path }
+    }
+}
+
+impl PatchReader for File {
+    async fn read(&self) -> Result<Patches> {
+        let content = read_to_string(&self.path)
+            .with_context(|| format!("Could not read file: {}", self.path))?;
+        Patches::parse(&content)
     }
 }
@@ -0,0 +1,209 @@
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+use reqwest::{RequestBuilder, Response};
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+
+use crate::core::{PatchReader, Patches};
+
+/// Reads the diff of a Gitea or Forgejo pull request.
+pub struct Gitea {
+    client: Client,
+    pull_number: u64,
+    /// Head SHA of the pull request at the time the patches were read.
+    read_sha: Mutex<Option<String>>,
+}
+
+/// Minimal client of the Gitea REST API, shared with `reporter::gitea`.
+/// Forgejo serves the same API.
+/// Ref: https://docs.gitea.com/api/1.22/
+pub struct Client {
+    client: reqwest::Client,
+    base_url: String,
+    token: Secret<String>,
+    owner: String,
+    repository: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequest {
+    pub head: Branch,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Branch {
+    pub sha: String,
+}
+
+/// The user the token belongs to, and author of the comments it posts.
+#[derive(Debug, Deserialize)]
+pub struct User {
+    pub login: String,
+}
+
+impl Client {
+    /// `base_url` is the URL of the instance, e.g. `https://codeberg.org`.
+    pub fn new(
+        base_url: String,
+        token: Secret<String>,
+        owner: String,
+        repository: String,
+    ) -> Client {
+        Client {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+            owner,
+            repository,
+        }
+    }
+
+    /// URL of the endpoint under the repository, e.g. `pulls/1/reviews`.
+    pub fn url(&self, path: &str) -> String {
+        format!(
+            "{}/api/v1/repos/{}/{}/{}",
+            self.base_url, self.owner, self.repository, path
+        )
+    }
+
+    pub fn get(&self, path: &str) -> RequestBuilder {
+        self.with_token(self.client.get(self.url(path)))
+    }
+
+    pub fn post(&self, path: &str) -> RequestBuilder {
+        self.with_token(self.client.post(self.url(path)))
+    }
+
+    pub fn patch(&self, path: &str) -> RequestBuilder {
+        self.with_token(self.client.patch(self.url(path)))
+    }
+
+    fn with_token(&self, request: RequestBuilder) -> RequestBuilder {
+        request.header(
+            "Authorization",
+            format!("token {}", self.token.expose_secret()),
+        )
+    }
+
+    /// Sends the request, turning error statuses into errors.
+    pub async fn send(request: RequestBuilder) -> Result<Response> {
+        let response = request.send().await?;
+        if response.status().is_client_error() || response.status().is_server_error() {
+            return Err(anyhow!(
+                "Gitea API returned {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+        Ok(response)
+    }
+
+    /// Fetches all pages of a list endpoint.
+    pub async fn get_all<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<Vec<T>> {
+        const LIMIT: usize = 50;
+        let mut all = Vec::new();
+        for page in 1.. {
+            let response =
+                Client::send(self.get(path).query(&[("limit", LIMIT), ("page", page)])).await?;
+            let values = response.json::<Vec<T>>().await?;
+            let last = values.len() < LIMIT;
+            all.extend(values);
+            if last {
+                break;
+            }
+        }
+        Ok(all)
+    }
+
+    pub async fn pull_request(&self, pull_number: u64) -> Result<PullRequest> {
+        let response = Client::send(self.get(&format!("pulls/{}", pull_number))).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Ref: https://docs.gitea.com/api/1.22/#tag/user/operation/userGetCurrent
+    pub async fn current_user(&self) -> Result<User> {
+        let request = self.with_token(self.client.get(format!("{}/api/v1/user", self.base_url)));
+        Ok(Client::send(request).await?.json().await?)
+    }
+}
+
+impl Gitea {
+    pub fn new(client: Client, pull_number: u64) -> Gitea {
+        Gitea {
+            client,
+            pull_number,
+            read_sha: Mutex::new(None),
+        }
+    }
+
+    /// Head SHA of the pull request at the time the patches were read, which the review
+    /// must be made on for its lines to match.
+    pub fn read_sha(&self) -> Option<String> {
+        self.read_sha.lock().unwrap().clone()
+    }
+}
+
+impl PatchReader for Gitea {
+    async fn read(&self) -> Result<Patches> {
+        let head_sha = self.client.pull_request(self.pull_number).await?.head.sha;
+        let response =
+            Client::send(self.client.get(&format!("pulls/{}.diff", self.pull_number))).await?;
+        let diff = response.text().await?;
+        *self.read_sha.lock().unwrap() = Some(head_sha);
+        if diff.trim().is_empty() {
+            return Ok(Patches { all: Vec::new() });
+        }
+        Patches::parse(&diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read() {
+        let mut server = mockito::Server::new_async().await;
+        let pull_request = server
+            .mock("GET", "/api/v1/repos/owner/repository/pulls/3")
+            .with_body(serde_json::json!({ "number": 3, "head": { "sha": "head" } }).to_string())
+            .create_async()
+            .await;
+        let diff = server
+            .mock("GET", "/api/v1/repos/owner/repository/pulls/3.diff")
+            .match_header("Authorization", "token token")
+            .with_body(indoc! {"
+                diff --git a/src/main.rs b/src/main.rs
+                index 0123456..789abcd 100644
+                --- a/src/main.rs
+                +++ b/src/main.rs
+                @@ -1,2 +1,3 @@
+                 fn main() {
+                +    println!(\"hello\");
+                 }
+            "})
+            .create_async()
+            .await;
+
+        let gitea = Gitea::new(
+            Client::new(
+                server.url(),
+                Secret::new("token".to_string()),
+                "owner".to_string(),
+                "repository".to_string(),
+            ),
+            3,
+        );
+        let patches = gitea.read().await.unwrap();
+
+        pull_request.assert_async().await;
+        diff.assert_async().await;
+        assert_eq!(gitea.read_sha().as_deref(), Some("head"));
+        assert_eq!(patches.all.len(), 1);
+        assert_eq!(patches.all[0].path, "src/main.rs");
+        assert!(patches.all[0].content.contains("+    println!(\"hello\");"));
+    }
+}
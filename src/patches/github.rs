@@ -1,13 +1,51 @@
+use std::sync::Mutex;
+
 use crate::core::{PatchReader, Patches};
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+use octocrab::models::CommentId;
 use octocrab::Octocrab;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT};
 use secrecy::Secret;
+use serde::{Deserialize, Serialize};
 
 pub struct Github {
     client: Octocrab,
     owner: String,
     repository: String,
     pull_number: u64,
+    incremental: bool,
+    /// Head SHA of the pull request at the time the patches were read.
+    read_sha: Mutex<Option<String>>,
+}
+
+/// Marks the PR comment that stores the state of the previous run.
+const STATE_MARKER: &str = "<!-- llm-linter-state:";
+
+/// Author of the comments posted with the token of GitHub Actions.
+const ACTIONS_BOT: &str = "github-actions[bot]";
+
+/// Login of the author of the comments posted with the token of the client. The token of
+/// GitHub Actions cannot read its own user, so it is assumed to be the Actions bot then.
+pub async fn login(client: &Octocrab) -> String {
+    match client.current().user().await {
+        Ok(user) => user.login,
+        Err(e) => {
+            debug!(
+                "Failed to read the authenticated user, assuming {}: {}",
+                ACTIONS_BOT, e
+            );
+            ACTIONS_BOT.to_string()
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    last_linted_sha: Option<String>,
+    /// Total cost of all runs on the pull request.
+    #[serde(default)]
+    spent: f64,
 }
 
 impl Github {
@@ -22,17 +60,196 @@ impl Github {
             owner,
             repository,
             pull_number,
+            incremental: false,
+            read_sha: Mutex::new(None),
+        }
+    }
+
+    /// Only reads the hunks touched since the head SHA recorded by `save_state`.
+    pub fn incremental(mut self) -> Github {
+        self.incremental = true;
+        self
+    }
+
+    /// Records the head SHA the patches were read at, so that the next incremental run
+    /// starts from there, and adds the cost of this run to the total of the pull request.
+    /// Unless `complete`, e.g. when hunks were skipped, the previous SHA is kept so that
+    /// the next run lints them again.
+    pub async fn save_state(&self, cost: f64, currency: &str, complete: bool) -> Result<()> {
+        let sha = self
+            .read_sha
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| anyhow!("Patches have not been read yet."))?;
+        let previous = self.find_state().await?;
+        let last_linted_sha = if complete {
+            Some(sha)
+        } else {
+            previous
+                .as_ref()
+                .and_then(|(_, state)| state.last_linted_sha.clone())
+        };
+        let state = State {
+            last_linted_sha: last_linted_sha.clone(),
+            spent: previous.as_ref().map_or(0.0, |(_, state)| state.spent) + cost,
+        };
+        let reviewed = match &last_linted_sha {
+            Some(sha) => format!("has reviewed this pull request up to {}", sha),
+            None => "has not fully reviewed this pull request yet".to_string(),
+        };
+        let body = format!(
+            "LLM linter {}, spending {:.4} {} in total.\n\n{} {} -->",
+            reviewed,
+            state.spent,
+            currency,
+            STATE_MARKER,
+            serde_json::to_string(&state)?
+        );
+
+        match previous {
+            Some((id, _)) => {
+                let route = format!(
+                    "/repos/{}/{}/issues/comments/{}",
+                    self.owner, self.repository, id
+                );
+                self.client
+                    .patch::<serde_json::Value, _, _>(
+                        route,
+                        Some(&serde_json::json!({ "body": body })),
+                    )
+                    .await?;
+            }
+            None => {
+                self.client
+                    .issues(self.owner.clone(), self.repository.clone())
+                    .create_comment(self.pull_number, body)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Head SHA of the pull request at the time the patches were read, which comments
+    /// must be made on for their lines to match.
+    pub fn read_sha(&self) -> Option<String> {
+        self.read_sha.lock().unwrap().clone()
+    }
+
+    /// Total cost of the previous runs on the pull request.
+    pub async fn spent(&self) -> Result<f64> {
+        Ok(self
+            .find_state()
+            .await?
+            .map_or(0.0, |(_, state)| state.spent))
+    }
+
+    async fn head_sha(&self) -> Result<String> {
+        let pull = self
+            .client
+            .pulls(self.owner.clone(), self.repository.clone())
+            .get(self.pull_number)
+            .await?;
+        Ok(pull.head.sha)
+    }
+
+    async fn find_state(&self) -> Result<Option<(CommentId, State)>> {
+        let page = self
+            .client
+            .issues(self.owner.clone(), self.repository.clone())
+            .list_comments(self.pull_number)
+            .per_page(100)
+            .send()
+            .await?;
+        let comments = self.client.all_pages(page).await?;
+
+        // Anyone can post a comment with the marker, so only our own are trusted.
+        let login = login(&self.client).await;
+        for comment in comments.into_iter().filter(|c| c.user.login == login) {
+            let body = comment.body.unwrap_or_default();
+            let Some(start) = body.find(STATE_MARKER) else {
+                continue;
+            };
+            let json = body[start + STATE_MARKER.len()..]
+                .split("-->")
+                .next()
+                .unwrap_or_default();
+            match serde_json::from_str::<State>(json.trim()) {
+                Ok(state) if !(state.spent >= 0.0 && state.spent.is_finite()) => {
+                    warn!(
+                        "Ignoring state comment {} with invalid spent cost: {}",
+                        comment.id, state.spent
+                    )
+                }
+                Ok(state) => return Ok(Some((comment.id, state))),
+                Err(e) => warn!("Ignoring broken state comment {}: {}", comment.id, e),
+            }
         }
+        Ok(None)
+    }
+
+    async fn compare_diff(&self, base: &str, head: &str) -> Result<String> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("application/vnd.github.v3.diff"),
+        );
+        let response = self
+            .client
+            ._get_with_headers(
+                format!(
+                    "/repos/{}/{}/compare/{}...{}",
+                    self.owner, self.repository, base, head
+                ),
+                Some(headers),
+            )
+            .await?;
+        let response = octocrab::map_github_error(response).await?;
+        Ok(self.client.body_to_string(response).await?)
     }
 }
 
 impl PatchReader for Github {
     async fn read(&self) -> Result<Patches> {
+        let head_sha = self.head_sha().await?;
         let patch = self
             .client
             .pulls(self.owner.clone(), self.repository.clone())
             .get_diff(self.pull_number)
             .await?;
-        Patches::parse(&patch)
+        let patches = Patches::parse(&patch)?;
+        *self.read_sha.lock().unwrap() = Some(head_sha.clone());
+
+        if !self.incremental {
+            return Ok(patches);
+        }
+
+        let Some(last_linted_sha) = self
+            .find_state()
+            .await?
+            .and_then(|(_, s)| s.last_linted_sha)
+        else {
+            info!("No previous run found, linting the whole pull request.");
+            return Ok(patches);
+        };
+        if last_linted_sha == head_sha {
+            info!("{} has already been linted.", head_sha);
+            return Ok(Patches { all: Vec::new() });
+        }
+
+        // The previous head is gone after a force push, so there is nothing to compare with.
+        match self.compare_diff(&last_linted_sha, &head_sha).await {
+            Ok(diff) => {
+                info!("Linting changes from {} to {}.", last_linted_sha, head_sha);
+                Ok(patches.touched_by(&Patches::parse(&diff)?))
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to compare {} with {}, linting the whole pull request: {}",
+                    last_linted_sha, head_sha, e
+                );
+                Ok(patches)
+            }
+        }
     }
 }
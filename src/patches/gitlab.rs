@@ -0,0 +1,250 @@
+use anyhow::{anyhow, Result};
+use log::warn;
+use reqwest::{RequestBuilder, Response};
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+
+use crate::core::{PatchReader, Patches};
+
+/// Reads the changes of a GitLab merge request.
+pub struct Gitlab {
+    client: Client,
+    merge_request_iid: u64,
+}
+
+/// Minimal client of the GitLab REST API, shared with `reporter::gitlab`.
+/// Ref: https://docs.gitlab.com/ee/api/rest/
+pub struct Client {
+    client: reqwest::Client,
+    base_url: String,
+    token: Secret<String>,
+    project: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeRequest {
+    pub diff_refs: DiffRefs,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiffRefs {
+    pub base_sha: String,
+    pub start_sha: String,
+    pub head_sha: String,
+}
+
+/// The user the token belongs to, and author of the notes it posts.
+#[derive(Debug, Deserialize)]
+pub struct User {
+    pub username: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Diff {
+    old_path: String,
+    new_path: String,
+    diff: String,
+    new_file: bool,
+    deleted_file: bool,
+}
+
+impl Client {
+    /// `base_url` is the URL of the instance, e.g. `https://gitlab.com`, and `project`
+    /// the path of the project, e.g. `group/subgroup/project`.
+    pub fn new(base_url: String, token: Secret<String>, project: String) -> Client {
+        Client {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+            project,
+        }
+    }
+
+    /// URL of the endpoint under the project, e.g. `merge_requests/1/notes`.
+    pub fn url(&self, path: &str) -> String {
+        format!(
+            "{}/api/v4/projects/{}/{}",
+            self.base_url,
+            self.project.replace('/', "%2F"),
+            path
+        )
+    }
+
+    pub fn get(&self, path: &str) -> RequestBuilder {
+        self.with_token(self.client.get(self.url(path)))
+    }
+
+    pub fn post(&self, path: &str) -> RequestBuilder {
+        self.with_token(self.client.post(self.url(path)))
+    }
+
+    pub fn put(&self, path: &str) -> RequestBuilder {
+        self.with_token(self.client.put(self.url(path)))
+    }
+
+    fn with_token(&self, request: RequestBuilder) -> RequestBuilder {
+        request.header("PRIVATE-TOKEN", self.token.expose_secret())
+    }
+
+    /// Sends the request, turning error statuses into errors.
+    pub async fn send(request: RequestBuilder) -> Result<Response> {
+        let response = request.send().await?;
+        if response.status().is_client_error() || response.status().is_server_error() {
+            return Err(anyhow!(
+                "GitLab API returned {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+        Ok(response)
+    }
+
+    /// Fetches all pages of a list endpoint.
+    pub async fn get_all<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<Vec<T>> {
+        let mut all = Vec::new();
+        let mut page = "1".to_string();
+        loop {
+            let response = Client::send(
+                self.get(path)
+                    .query(&[("per_page", "100"), ("page", page.as_str())]),
+            )
+            .await?;
+            let next_page = response
+                .headers()
+                .get("x-next-page")
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            all.extend(response.json::<Vec<T>>().await?);
+            if next_page.is_empty() {
+                return Ok(all);
+            }
+            page = next_page;
+        }
+    }
+
+    pub async fn merge_request(&self, iid: u64) -> Result<MergeRequest> {
+        let response = Client::send(self.get(&format!("merge_requests/{}", iid))).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Ref: https://docs.gitlab.com/ee/api/users.html#for-non-administrator-users
+    pub async fn current_user(&self) -> Result<User> {
+        let request = self.with_token(self.client.get(format!("{}/api/v4/user", self.base_url)));
+        Ok(Client::send(request).await?.json().await?)
+    }
+}
+
+impl Gitlab {
+    pub fn new(client: Client, merge_request_iid: u64) -> Gitlab {
+        Gitlab {
+            client,
+            merge_request_iid,
+        }
+    }
+}
+
+impl PatchReader for Gitlab {
+    async fn read(&self) -> Result<Patches> {
+        let diffs = self
+            .client
+            .get_all::<Diff>(&format!("merge_requests/{}/diffs", self.merge_request_iid))
+            .await?;
+
+        // GitLab returns the hunks of each file without the headers of a unified diff.
+        let mut content = String::new();
+        for diff in diffs {
+            if diff.deleted_file {
+                continue;
+            }
+            if diff.diff.is_empty() {
+                warn!(
+                    "Skipping {}: no diff, e.g. a binary file or one too large",
+                    diff.new_path
+                );
+                continue;
+            }
+            let old_path = if diff.new_file {
+                "/dev/null".to_string()
+            } else {
+                format!("a/{}", diff.old_path)
+            };
+            content.push_str(&format!("--- {}\n+++ b/{}\n", old_path, diff.new_path));
+            content.push_str(&diff.diff);
+            if !diff.diff.ends_with('\n') {
+                content.push('\n');
+            }
+        }
+
+        if content.is_empty() {
+            return Ok(Patches { all: Vec::new() });
+        }
+        Patches::parse(&content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read() {
+        let mut server = mockito::Server::new_async().await;
+        let diffs = server
+            .mock(
+                "GET",
+                "/api/v4/projects/group%2Fproject/merge_requests/7/diffs",
+            )
+            .match_query(mockito::Matcher::UrlEncoded("page".into(), "1".into()))
+            .match_header("PRIVATE-TOKEN", "token")
+            .with_body(
+                serde_json::json!([
+                    {
+                        "old_path": "src/main.rs",
+                        "new_path": "src/main.rs",
+                        "diff": "@@ -1,2 +1,3 @@\n fn main() {\n+    println!(\"hello\");\n }\n",
+                        "new_file": false,
+                        "deleted_file": false
+                    },
+                    {
+                        "old_path": "old.rs",
+                        "new_path": "old.rs",
+                        "diff": "@@ -1 +0,0 @@\n-fn old() {}\n",
+                        "new_file": false,
+                        "deleted_file": true
+                    },
+                    {
+                        "old_path": "README.md",
+                        "new_path": "README.md",
+                        "diff": "@@ -0,0 +1 @@\n+# Title\n",
+                        "new_file": true,
+                        "deleted_file": false
+                    }
+                ])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let gitlab = Gitlab::new(
+            Client::new(
+                server.url(),
+                Secret::new("token".to_string()),
+                "group/project".to_string(),
+            ),
+            7,
+        );
+        let patches = gitlab.read().await.unwrap();
+
+        diffs.assert_async().await;
+        assert_eq!(
+            patches
+                .all
+                .iter()
+                .map(|patch| (patch.path.as_str(), patch.start_line))
+                .collect::<Vec<_>>(),
+            vec![("src/main.rs", 1), ("README.md", 1)]
+        );
+        assert!(patches.all[0].content.contains("+    println!(\"hello\");"));
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::core::Usage;
+
+/// Prices of models per 1M tokens, in any single currency.
+#[derive(Debug, Deserialize)]
+pub struct Pricing {
+    pub currency: String,
+    pub models: HashMap<String, Price>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct Price {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl Default for Pricing {
+    // Ref: https://openai.com/api/pricing/
+    fn default() -> Pricing {
+        Pricing {
+            currency: "USD".to_string(),
+            models: [
+                ("gpt-4o", 2.5, 10.0),
+                ("gpt-4o-2024-05-13", 5.0, 15.0),
+                ("gpt-4o-mini", 0.15, 0.6),
+                ("gpt-4-turbo", 10.0, 30.0),
+                ("gpt-4", 30.0, 60.0),
+                ("gpt-3.5-turbo", 0.5, 1.5),
+            ]
+            .into_iter()
+            .map(|(model, input, output)| (model.to_string(), Price { input, output }))
+            .collect(),
+        }
+    }
+}
+
+impl Pricing {
+    /// Reads a pricing table, e.g. `{"currency": "JPY", "models": {"gpt-4o": {"input": 375, "output": 1500}}}`.
+    /// It replaces the default table as a whole, so that currencies are never mixed.
+    pub fn read(path: &str) -> Result<Pricing> {
+        let content =
+            read_to_string(path).with_context(|| format!("Could not read file: {}", path))?;
+        serde_json::from_str(&content).with_context(|| format!("Invalid pricing: {}", path))
+    }
+
+    /// Finds the price of the model, falling back to the longest model name it starts with
+    /// (e.g. `gpt-4o-2024-08-06` is priced as `gpt-4o`).
+    pub fn price_of(&self, model: &str) -> Option<Price> {
+        self.models.get(model).copied().or_else(|| {
+            self.models
+                .iter()
+                .filter(|(name, _)| model.starts_with(name.as_str()))
+                .max_by_key(|(name, _)| name.len())
+                .map(|(_, price)| *price)
+        })
+    }
+
+    pub fn cost_of(&self, model: &str, usage: &Usage) -> Option<f64> {
+        self.price_of(model).map(|price| {
+            (usage.prompt_tokens as f64 * price.input
+                + usage.completion_tokens as f64 * price.output)
+                / 1_000_000.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost_of() {
+        let pricing = Pricing::default();
+        let usage = Usage {
+            prompt_tokens: 1_000_000,
+            completion_tokens: 100_000,
+        };
+
+        assert_eq!(pricing.cost_of("gpt-4o", &usage), Some(3.5));
+        assert_eq!(pricing.cost_of("gpt-4o-2024-08-06", &usage), Some(3.5));
+        assert_eq!(
+            pricing.cost_of("gpt-4o-mini-2024-07-18", &usage),
+            Some(0.21)
+        );
+        assert_eq!(pricing.cost_of("claude", &usage), None);
+    }
+}
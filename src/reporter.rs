@@ -1,2 +1,47 @@
+use std::fs::write;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+
+pub mod actions;
+pub mod checkstyle;
+pub mod fan_out;
+pub mod gerrit;
+pub mod gitea;
 pub mod github;
+pub mod gitlab;
+pub mod html;
+pub mod junit;
+pub mod rdjson;
 pub mod stdout;
+
+/// Writes the report of a file-based reporter to `output`, or prints it to stdout if `None`.
+fn write_output(output: &Option<PathBuf>, report: &str) -> Result<()> {
+    match output {
+        Some(path) => {
+            write(path, report).with_context(|| format!("Could not write file: {}", path.display()))
+        }
+        None => {
+            println!("{}", report);
+            Ok(())
+        }
+    }
+}
+
+/// Fails a reporter of a code review platform if any of its requests failed. The reporter
+/// keeps going after a failure, logging it, so that as much as possible is reported.
+fn check_failures(platform: &str, failures: usize) -> Result<()> {
+    if failures > 0 {
+        return Err(anyhow!("{} requests to {} failed", failures, platform));
+    }
+    Ok(())
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,115 @@
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use anyhow::Result;
+use log::warn;
+
+use crate::core::{Indication, IndicationKind, Indications, Reporter};
+use crate::summary::Summary;
+
+/// Reports through GitHub Actions workflow commands, which show up as annotations on
+/// the pull request, and the job summary. Unlike `GithubReporter`, this needs no token
+/// with write permissions, so it also works for pull requests from forks.
+pub struct ActionsReporter {}
+
+impl Reporter for ActionsReporter {
+    async fn report(&self, indications: &Indications, summary: &Summary) -> Result<()> {
+        for indication in &indications.values {
+            println!("{}", workflow_command(indication));
+        }
+
+        match env::var("GITHUB_STEP_SUMMARY") {
+            Ok(path) => {
+                let result = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .and_then(|mut file| {
+                        writeln!(file, "{}", self.step_summary(indications, summary))
+                    });
+                if let Err(e) = result {
+                    warn!("Failed to write the job summary to {}: {}", path, e);
+                }
+            }
+            Err(_) => warn!("GITHUB_STEP_SUMMARY is not set, skipping the job summary"),
+        }
+        Ok(())
+    }
+}
+
+impl ActionsReporter {
+    pub fn new() -> ActionsReporter {
+        ActionsReporter {}
+    }
+
+    fn step_summary(&self, indications: &Indications, summary: &Summary) -> String {
+        let mut lines = vec!["## LLM linter".to_string(), String::new()];
+        if indications.values.is_empty() {
+            lines.push("No indications.".to_string());
+        } else {
+            lines.push("| File | Lines | Kind | Message |".to_string());
+            lines.push("| --- | --- | --- | --- |".to_string());
+            for indication in &indications.values {
+                lines.push(format!(
+                    "| `{}` | {}-{} | {:?} | {} |",
+                    indication.location.path,
+                    indication.location.start_line,
+                    indication.location.end_line,
+                    indication.kind,
+                    indication.message.replace('|', "\\|").replace('\n', "<br>")
+                ));
+            }
+        }
+        lines.push(String::new());
+        lines.push(summary.to_markdown());
+        lines.join("\n")
+    }
+}
+
+/// Ref: https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions
+fn workflow_command(indication: &Indication) -> String {
+    let command = match indication.kind {
+        IndicationKind::Error => "error",
+        IndicationKind::Warning | IndicationKind::Cancel => "warning",
+        IndicationKind::Info | IndicationKind::Hint => "notice",
+    };
+    format!(
+        "::{} file={},line={},endLine={},title={}::{}",
+        command,
+        escape_property(&indication.location.path),
+        indication.location.start_line,
+        indication.location.end_line,
+        escape_property("LLM linter"),
+        escape_data(&indication.message)
+    )
+}
+
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workflow_command() {
+        let indication = Indication {
+            message: "Use `expect`, not `unwrap`:\n100% sure".to_string(),
+            ..Indication::for_test("src/a,b.rs", 3, 4)
+        };
+
+        assert_eq!(
+            workflow_command(&indication),
+            "::error file=src/a%2Cb.rs,line=3,endLine=4,title=LLM linter::Use `expect`, not `unwrap`:%0A100%25 sure"
+        );
+    }
+}
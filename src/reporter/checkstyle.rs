@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::{escape_xml, write_output};
+use crate::core::{Indication, IndicationKind, Indications, Reporter};
+use crate::summary::Summary;
+
+/// Writes Checkstyle XML, which Jenkins (Warnings Next Generation) and many other CI
+/// dashboards can show.
+pub struct CheckstyleReporter {
+    /// Prints to stdout if `None`.
+    output: Option<PathBuf>,
+}
+
+impl Reporter for CheckstyleReporter {
+    async fn report(&self, indications: &Indications, _summary: &Summary) -> Result<()> {
+        write_output(&self.output, &checkstyle(indications))
+    }
+}
+
+impl CheckstyleReporter {
+    pub fn new(output: Option<PathBuf>) -> CheckstyleReporter {
+        CheckstyleReporter { output }
+    }
+}
+
+fn checkstyle(indications: &Indications) -> String {
+    let mut files: BTreeMap<&str, Vec<&Indication>> = BTreeMap::new();
+    for indication in &indications.values {
+        files
+            .entry(&indication.location.path)
+            .or_default()
+            .push(indication);
+    }
+
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+        r#"<checkstyle version="4.3">"#.to_string(),
+    ];
+    for (path, indications) in files {
+        lines.push(format!(r#"  <file name="{}">"#, escape_xml(path)));
+        for indication in indications {
+            lines.push(format!(
+                r#"    <error line="{}" severity="{}" message="{}" source="{}"/>"#,
+                indication.location.start_line,
+                match indication.kind {
+                    IndicationKind::Error => "error",
+                    IndicationKind::Warning | IndicationKind::Cancel => "warning",
+                    IndicationKind::Info | IndicationKind::Hint => "info",
+                },
+                escape_xml(&indication.message),
+                escape_xml(&match &indication.rule {
+                    Some(rule) => format!("llm-linter.{}", rule),
+                    None => "llm-linter".to_string(),
+                })
+            ));
+        }
+        lines.push("  </file>".to_string());
+    }
+    lines.push("</checkstyle>".to_string());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn test_checkstyle() {
+        let indication = |path: &str, kind, rule: Option<&str>| Indication {
+            kind,
+            message: "Use \"expect\" & <explain>".to_string(),
+            rule: rule.map(str::to_string),
+            ..Indication::for_test(path, 3, 4)
+        };
+        let indications = Indications {
+            values: vec![
+                indication("src/main.rs", IndicationKind::Error, Some("for-rust")),
+                indication("README.md", IndicationKind::Warning, None),
+                indication("src/main.rs", IndicationKind::Warning, Some("for-rust")),
+            ],
+        };
+
+        assert_eq!(
+            checkstyle(&indications),
+            indoc! {r#"
+                <?xml version="1.0" encoding="UTF-8"?>
+                <checkstyle version="4.3">
+                  <file name="README.md">
+                    <error line="3" severity="warning" message="Use &quot;expect&quot; &amp; &lt;explain&gt;" source="llm-linter"/>
+                  </file>
+                  <file name="src/main.rs">
+                    <error line="3" severity="error" message="Use &quot;expect&quot; &amp; &lt;explain&gt;" source="llm-linter.for-rust"/>
+                    <error line="3" severity="warning" message="Use &quot;expect&quot; &amp; &lt;explain&gt;" source="llm-linter.for-rust"/>
+                  </file>
+                </checkstyle>"#}
+        );
+    }
+}
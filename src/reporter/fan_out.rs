@@ -0,0 +1,236 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use super::actions::ActionsReporter;
+use super::checkstyle::CheckstyleReporter;
+use super::gerrit::GerritReporter;
+use super::gitea::GiteaReporter;
+use super::github::GithubReporter;
+use super::gitlab::GitlabReporter;
+use super::html::HtmlReporter;
+use super::junit::JunitReporter;
+use super::rdjson::RdjsonReporter;
+use super::stdout::StdoutReporter;
+use log::error;
+
+use crate::core::{IndicationKind, Indications, Reporter};
+use crate::summary::Summary;
+
+/// Forwards the indications to several reporters, each keeping only the indications at
+/// least as severe as its own threshold.
+pub struct FanOut {
+    reporters: Vec<(AnyReporter, IndicationKind)>,
+}
+
+impl Reporter for FanOut {
+    /// Runs every reporter even if one fails, and returns the first failure.
+    async fn report(&self, indications: &Indications, summary: &Summary) -> Result<()> {
+        let mut result = Ok(());
+        for (reporter, min_kind) in &self.reporters {
+            let reported = reporter
+                .report(&indications.at_least(*min_kind), summary)
+                .await;
+            if let Err(e) = reported {
+                error!("Failed to report: {:#}", e);
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+}
+
+impl FanOut {
+    pub fn new() -> FanOut {
+        FanOut {
+            reporters: Vec::new(),
+        }
+    }
+
+    pub fn with_reporter(mut self, reporter: AnyReporter, min_kind: IndicationKind) -> FanOut {
+        self.reporters.push((reporter, min_kind));
+        self
+    }
+}
+
+/// `Reporter` uses async functions, so it cannot be a trait object.
+pub enum AnyReporter {
+    Actions(ActionsReporter),
+    Checkstyle(CheckstyleReporter),
+    Gerrit(GerritReporter),
+    Gitea(GiteaReporter),
+    Github(GithubReporter),
+    Gitlab(GitlabReporter),
+    Html(HtmlReporter),
+    Junit(JunitReporter),
+    Rdjson(RdjsonReporter),
+    Stdout(StdoutReporter),
+}
+
+impl Reporter for AnyReporter {
+    async fn report(&self, indications: &Indications, summary: &Summary) -> Result<()> {
+        match self {
+            AnyReporter::Actions(reporter) => reporter.report(indications, summary).await,
+            AnyReporter::Checkstyle(reporter) => reporter.report(indications, summary).await,
+            AnyReporter::Gerrit(reporter) => reporter.report(indications, summary).await,
+            AnyReporter::Gitea(reporter) => reporter.report(indications, summary).await,
+            AnyReporter::Github(reporter) => reporter.report(indications, summary).await,
+            AnyReporter::Gitlab(reporter) => reporter.report(indications, summary).await,
+            AnyReporter::Html(reporter) => reporter.report(indications, summary).await,
+            AnyReporter::Junit(reporter) => reporter.report(indications, summary).await,
+            AnyReporter::Rdjson(reporter) => reporter.report(indications, summary).await,
+            AnyReporter::Stdout(reporter) => reporter.report(indications, summary).await,
+        }
+    }
+}
+
+pub const REPORTERS: &[&str] = &[
+    "github",
+    "gitlab",
+    "gitea",
+    "actions",
+    "gerrit",
+    "rdjson",
+    "rdjsonl",
+    "checkstyle",
+    "junit",
+    "html",
+    "stdout",
+];
+
+/// A reporter given as `NAME[:SEVERITY][=FILE]` on the command line,
+/// e.g. `github`, `rdjson:warning=lint.json` or `html=report.html`.
+#[derive(Debug, PartialEq)]
+pub struct ReporterSpec {
+    pub name: String,
+    /// Least severe indications reported.
+    pub min_kind: IndicationKind,
+    /// File a file-based reporter writes to, instead of `--output`.
+    pub output: Option<PathBuf>,
+}
+
+impl ReporterSpec {
+    /// `min_kind` replaces the default threshold of the reporter when no severity is given.
+    pub fn parse(spec: &str, min_kind: Option<IndicationKind>) -> Result<ReporterSpec> {
+        let (reporter, output) = match spec.split_once('=') {
+            Some((reporter, output)) => (reporter, Some(PathBuf::from(output))),
+            None => (spec, None),
+        };
+        let (name, severity) = match reporter.split_once(':') {
+            Some((name, severity)) => (name, Some(severity)),
+            None => (reporter, None),
+        };
+        if !REPORTERS.contains(&name) {
+            return Err(anyhow!("Unknown reporter: {}", name));
+        }
+        let min_kind = match severity {
+            Some(severity) => IndicationKind::parse(severity)?,
+            None => min_kind.unwrap_or_else(|| default_min_kind(name)),
+        };
+        Ok(ReporterSpec {
+            name: name.to_string(),
+            min_kind,
+            output,
+        })
+    }
+}
+
+/// Rejects reporters writing to the same file, or several ones printing to stdout,
+/// since their reports would overwrite or mix with each other. `output` is the file
+/// given with `--output`, used by file-based reporters given without one.
+pub fn check_destinations(specs: &[ReporterSpec], output: Option<&Path>) -> Result<()> {
+    let mut seen: Vec<(Option<&Path>, &str)> = Vec::new();
+    for spec in specs {
+        let destination = match spec.name.as_str() {
+            "github" | "gitlab" | "gitea" => continue,
+            "actions" | "stdout" => None,
+            _ => spec.output.as_deref().or(output),
+        };
+        if let Some((_, other)) = seen.iter().find(|(seen, _)| *seen == destination) {
+            return Err(match destination {
+                Some(path) => anyhow!(
+                    "The {} and {} reporters cannot both write to {}",
+                    other,
+                    spec.name,
+                    path.display()
+                ),
+                None => anyhow!(
+                    "The {} and {} reporters cannot both print to stdout, give one of them =FILE",
+                    other,
+                    spec.name
+                ),
+            });
+        }
+        seen.push((destination, &spec.name));
+    }
+    Ok(())
+}
+
+/// Comments on the pull request only point out errors, while files meant for other tools
+/// keep warnings, and the HTML report also lists the indications the model cancelled.
+fn default_min_kind(name: &str) -> IndicationKind {
+    match name {
+        "github" | "gitlab" | "gitea" | "actions" | "gerrit" => IndicationKind::Error,
+        "html" => IndicationKind::Cancel,
+        _ => IndicationKind::Warning,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            ReporterSpec::parse("github", None).unwrap(),
+            ReporterSpec {
+                name: "github".to_string(),
+                min_kind: IndicationKind::Error,
+                output: None,
+            }
+        );
+        assert_eq!(
+            ReporterSpec::parse("rdjson:error=out/lint:1.json", Some(IndicationKind::Cancel))
+                .unwrap(),
+            ReporterSpec {
+                name: "rdjson".to_string(),
+                min_kind: IndicationKind::Error,
+                output: Some(PathBuf::from("out/lint:1.json")),
+            }
+        );
+        assert_eq!(
+            ReporterSpec::parse("html=report.html", None)
+                .unwrap()
+                .min_kind,
+            IndicationKind::Cancel
+        );
+        assert_eq!(
+            ReporterSpec::parse("github", Some(IndicationKind::Warning))
+                .unwrap()
+                .min_kind,
+            IndicationKind::Warning
+        );
+        assert!(ReporterSpec::parse("sarif", None).is_err());
+        assert!(ReporterSpec::parse("stdout:fatal", None).is_err());
+    }
+
+    #[test]
+    fn test_check_destinations() {
+        let specs = |specs: &[&str]| {
+            specs
+                .iter()
+                .map(|spec| ReporterSpec::parse(spec, None).unwrap())
+                .collect::<Vec<ReporterSpec>>()
+        };
+        let output = Some(Path::new("lint.json"));
+
+        assert!(check_destinations(&specs(&["github", "gitea", "stdout"]), None).is_ok());
+        assert!(check_destinations(&specs(&["rdjson", "html=report.html"]), output).is_ok());
+        assert!(check_destinations(&specs(&["html=a.html", "junit=a.html"]), None).is_err());
+        assert!(check_destinations(&specs(&["rdjson", "checkstyle"]), output).is_err());
+        assert!(check_destinations(&specs(&["rdjson", "checkstyle=a.xml"]), None).is_ok());
+        assert!(check_destinations(&specs(&["rdjson", "stdout"]), None).is_err());
+        assert!(check_destinations(&specs(&["actions", "stdout"]), None).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::write_output;
+use crate::core::{Indication, Indications, Patches, Reporter};
+use crate::summary::Summary;
+
+/// Writes a Gerrit `ReviewInput` with robot comments, for the CI to post with
+/// `POST /changes/{change-id}/revisions/{revision-id}/review`.
+/// Ref: https://gerrit-review.googlesource.com/Documentation/rest-api-changes.html#review-input
+pub struct GerritReporter {
+    /// Prints to stdout if `None`.
+    output: Option<PathBuf>,
+    /// Identifies the run among the robot comments, the current UNIX time.
+    run_id: String,
+    /// Whole hunks of the change, to find where the last line of a range ends.
+    patches: Patches,
+}
+
+impl Reporter for GerritReporter {
+    async fn report(&self, indications: &Indications, summary: &Summary) -> Result<()> {
+        let review_input =
+            serde_json::to_string_pretty(&self.review_input(indications, summary)).unwrap();
+        write_output(&self.output, &review_input)
+    }
+}
+
+impl GerritReporter {
+    pub fn new(output: Option<PathBuf>, patches: Patches) -> GerritReporter {
+        GerritReporter {
+            output,
+            run_id: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs().to_string())
+                .unwrap_or_default(),
+            patches,
+        }
+    }
+
+    fn review_input(&self, indications: &Indications, summary: &Summary) -> ReviewInput {
+        let mut robot_comments: BTreeMap<String, Vec<RobotCommentInput>> = BTreeMap::new();
+        for indication in &indications.values {
+            robot_comments
+                .entry(indication.location.path.clone())
+                .or_default()
+                .push(self.robot_comment(indication));
+        }
+
+        ReviewInput {
+            message: format!(
+                "LLM linter found {} issues.\n\n{}",
+                indications.values.len(),
+                summary
+            ),
+            // Lets Gerrit tell the comments of bots apart from those of humans.
+            tag: "autogenerated:llm-linter".to_string(),
+            robot_comments,
+        }
+    }
+
+    fn robot_comment(&self, indication: &Indication) -> RobotCommentInput {
+        let location = &indication.location;
+        RobotCommentInput {
+            robot_id: "llm-linter".to_string(),
+            robot_run_id: self.run_id.clone(),
+            line: location.end_line,
+            // The range must end on `line`, so it covers the last line up to its length.
+            // Without the line in the hunks, the comment is left on `line` alone.
+            range: self
+                .line_length(&location.path, location.end_line)
+                .filter(|_| !location.is_single_line())
+                .map(|end_character| CommentRange {
+                    start_line: location.start_line,
+                    start_character: 0,
+                    end_line: location.end_line,
+                    end_character,
+                }),
+            message: indication.message.clone(),
+            url: "https://github.com/HosokawaR/LLM-linter".to_string(),
+            properties: indication
+                .fingerprint
+                .iter()
+                .map(|fingerprint| ("fingerprint".to_string(), fingerprint.clone()))
+                .collect(),
+        }
+    }
+
+    /// Length in characters of the code on the line, without the diff marker.
+    fn line_length(&self, path: &str, line_number: u64) -> Option<u64> {
+        self.patches
+            .all
+            .iter()
+            .filter(|patch| patch.path == path)
+            .flat_map(|patch| patch.diff_lines())
+            .find(|(number, _, _)| *number == line_number)
+            .map(|(_, _, code)| code.chars().count() as u64)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ReviewInput {
+    message: String,
+    tag: String,
+    robot_comments: BTreeMap<String, Vec<RobotCommentInput>>,
+}
+
+#[derive(Debug, Serialize)]
+struct RobotCommentInput {
+    robot_id: String,
+    robot_run_id: String,
+    line: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range: Option<CommentRange>,
+    message: String,
+    url: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    properties: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+struct CommentRange {
+    start_line: u64,
+    start_character: u64,
+    end_line: u64,
+    end_character: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn test_review_input() {
+        let indication = |path: &str, start_line, end_line| Indication {
+            message: "Use `expect`".to_string(),
+            fingerprint: (path == "src/lib.rs").then(|| "0a1b".to_string()),
+            ..Indication::for_test(path, start_line, end_line)
+        };
+        let indications = Indications {
+            values: vec![
+                indication("src/main.rs", 3, 4),
+                indication("src/lib.rs", 7, 7),
+                indication("src/main.rs", 10, 10),
+                // Out of the hunks, so the range is unknown.
+                indication("src/main.rs", 20, 21),
+            ],
+        };
+        let reporter = GerritReporter {
+            output: None,
+            run_id: "42".to_string(),
+            patches: Patches::parse(indoc! {"
+                --- a/src/main.rs
+                +++ b/src/main.rs
+                @@ -3,2 +3,2 @@
+                 fn main() {
+                -    a();
+                +    b();
+            "})
+            .unwrap(),
+        };
+
+        let review_input =
+            serde_json::to_value(reporter.review_input(&indications, &Summary::default())).unwrap();
+
+        assert_eq!(review_input["tag"], "autogenerated:llm-linter");
+        assert_eq!(
+            review_input["robot_comments"],
+            serde_json::json!({
+                "src/lib.rs": [{
+                    "robot_id": "llm-linter",
+                    "robot_run_id": "42",
+                    "line": 7,
+                    "message": "Use `expect`",
+                    "url": "https://github.com/HosokawaR/LLM-linter",
+                    "properties": { "fingerprint": "0a1b" }
+                }],
+                "src/main.rs": [{
+                    "robot_id": "llm-linter",
+                    "robot_run_id": "42",
+                    "line": 4,
+                    "range": { "start_line": 3, "start_character": 0, "end_line": 4, "end_character": 8 },
+                    "message": "Use `expect`",
+                    "url": "https://github.com/HosokawaR/LLM-linter"
+                }, {
+                    "robot_id": "llm-linter",
+                    "robot_run_id": "42",
+                    "line": 10,
+                    "message": "Use `expect`",
+                    "url": "https://github.com/HosokawaR/LLM-linter"
+                }, {
+                    "robot_id": "llm-linter",
+                    "robot_run_id": "42",
+                    "line": 21,
+                    "message": "Use `expect`",
+                    "url": "https://github.com/HosokawaR/LLM-linter"
+                }]
+            })
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use anyhow::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use super::check_failures;
+use crate::core::{Indication, Indications, Patches, Reporter};
+use crate::patches::gitea::{Client, User};
+use crate::summary::Summary;
+
+/// Marks the PR comment holding the summary, which is updated on every run.
+const SUMMARY_MARKER: &str = "<!-- llm-linter-summary -->";
+
+/// Reports indications as a review of a Gitea or Forgejo pull request.
+pub struct GiteaReporter {
+    client: Client,
+    pull_number: u64,
+    commit_sha: Option<String>,
+    /// Whole hunks of the pull request, to place the comments on the old or new side.
+    patches: Patches,
+}
+
+impl Reporter for GiteaReporter {
+    async fn report(&self, indications: &Indications, summary: &Summary) -> Result<()> {
+        let mut failures = 0;
+        if !indications.values.is_empty() {
+            if let Err(e) = self.review(&indications.values).await {
+                warn!("Failed to post the review: {}", e);
+                for indication in &indications.values {
+                    warn!(
+                        "indication: {:?}",
+                        serde_json::to_string(indication).unwrap()
+                    );
+                }
+                failures += 1;
+            }
+        }
+        if let Err(e) = self.summarize(summary).await {
+            warn!("Failed to post the summary: {}", e);
+            failures += 1;
+        }
+        check_failures("Gitea", failures)
+    }
+}
+
+impl GiteaReporter {
+    pub fn new(client: Client, pull_number: u64, patches: Patches) -> GiteaReporter {
+        GiteaReporter {
+            client,
+            pull_number,
+            commit_sha: None,
+            patches,
+        }
+    }
+
+    /// Reviews the given commit instead of looking up the head of the pull request.
+    pub fn with_commit_sha(mut self, commit_sha: Option<String>) -> GiteaReporter {
+        self.commit_sha = commit_sha;
+        self
+    }
+
+    /// Posts all indications as comments of a single review.
+    async fn review(&self, indications: &[Indication]) -> Result<()> {
+        let commit_id = match &self.commit_sha {
+            Some(sha) => sha.clone(),
+            None => self.client.pull_request(self.pull_number).await?.head.sha,
+        };
+        let request = ReviewRequest {
+            body: add_suffix(format!("LLM linter found {} issues.", indications.len())),
+            event: "COMMENT".to_string(),
+            commit_id,
+            comments: indications
+                .iter()
+                .map(|indication| self.review_comment(indication))
+                .collect(),
+        };
+
+        Client::send(
+            self.client
+                .post(&format!("pulls/{}/reviews", self.pull_number))
+                .json(&request),
+        )
+        .await?;
+        Ok(())
+    }
+
+    fn review_comment(&self, indication: &Indication) -> ReviewComment {
+        let location = &indication.location;
+        // Gitea has no multi-line comments, so they go on the last line like on GitHub.
+        // Removed lines only have a position on the old side.
+        let (old_position, new_position) = self
+            .patches
+            .all
+            .iter()
+            .filter(|patch| patch.path == location.path)
+            .find_map(|patch| patch.sides_of(location.end_line))
+            .unwrap_or((None, Some(location.end_line)));
+        ReviewComment {
+            path: location.path.clone(),
+            body: indication.message.clone(),
+            old_position,
+            new_position,
+        }
+    }
+
+    /// Posts the summary as a PR comment, updating the one of the previous run if any.
+    /// Only our own comment is updated, not one quoting the marker by someone else.
+    async fn summarize(&self, summary: &Summary) -> Result<()> {
+        let body = add_suffix(format!(
+            "{}\n## LLM linter summary\n\n{}",
+            SUMMARY_MARKER,
+            summary.to_markdown()
+        ));
+
+        let user = self.client.current_user().await?;
+        let previous = self
+            .client
+            .get_all::<Comment>(&format!("issues/{}/comments", self.pull_number))
+            .await?
+            .into_iter()
+            .find(|comment| {
+                comment.user.login == user.login && comment.body.starts_with(SUMMARY_MARKER)
+            });
+
+        let request = match previous {
+            Some(comment) => self
+                .client
+                .patch(&format!("issues/comments/{}", comment.id)),
+            None => self
+                .client
+                .post(&format!("issues/{}/comments", self.pull_number)),
+        };
+        Client::send(request.json(&serde_json::json!({ "body": body }))).await?;
+        Ok(())
+    }
+}
+
+fn add_suffix(message: String) -> String {
+    format!(
+        "{}\n\n{}",
+        message, "Reported by [LLM linter](https://github.com/HosokawaR/LLM-linter)"
+    )
+}
+
+/// Ref: https://docs.gitea.com/api/1.22/#tag/repository/operation/repoCreatePullReview
+#[derive(Debug, Serialize)]
+struct ReviewRequest {
+    body: String,
+    event: String,
+    commit_id: String,
+    comments: Vec<ReviewComment>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReviewComment {
+    path: String,
+    body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old_position: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_position: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Comment {
+    id: u64,
+    body: String,
+    user: User,
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use mockito::Matcher;
+    use secrecy::Secret;
+
+    use super::*;
+    use crate::core::IndicationKind;
+
+    #[tokio::test]
+    async fn test_report() {
+        let mut server = mockito::Server::new_async().await;
+        let pull_request = server
+            .mock("GET", "/api/v1/repos/owner/repository/pulls/3")
+            .with_body(serde_json::json!({ "number": 3, "head": { "sha": "head" } }).to_string())
+            .create_async()
+            .await;
+        let review = server
+            .mock("POST", "/api/v1/repos/owner/repository/pulls/3/reviews")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "event": "COMMENT",
+                "commit_id": "head",
+                "comments": [
+                    { "path": "src/main.rs", "body": "Use `expect`", "old_position": 3 },
+                    {
+                        "path": "src/main.rs",
+                        "body": "Use `expect`",
+                        "old_position": 4,
+                        "new_position": 4
+                    }
+                ]
+            })))
+            .create_async()
+            .await;
+        let user = server
+            .mock("GET", "/api/v1/user")
+            .with_body(serde_json::json!({ "login": "linter" }).to_string())
+            .create_async()
+            .await;
+        let comments = server
+            .mock("GET", "/api/v1/repos/owner/repository/issues/3/comments")
+            .match_query(Matcher::Any)
+            // A summary quoted by someone else is not ours to update.
+            .with_body(
+                serde_json::json!([
+                    { "id": 1, "body": "LGTM", "user": { "login": "alice" } },
+                    { "id": 2, "body": SUMMARY_MARKER, "user": { "login": "alice" } }
+                ])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let create = server
+            .mock("POST", "/api/v1/repos/owner/repository/issues/3/comments")
+            .match_body(Matcher::Regex(SUMMARY_MARKER.to_string()))
+            .create_async()
+            .await;
+
+        let indication = |start_line, end_line| Indication {
+            kind: IndicationKind::Error,
+            message: "Use `expect`".to_string(),
+            ..Indication::for_test("src/main.rs", start_line, end_line)
+        };
+        // One indication ends on a removed line, the other on an unchanged one.
+        let patches = || {
+            Patches::parse(indoc! {"
+                --- a/src/main.rs
+                +++ b/src/main.rs
+                @@ -2,3 +2,3 @@
+                 fn main() {
+                -    a();
+                +    b();
+                 }
+            "})
+            .unwrap()
+        };
+        GiteaReporter::new(
+            Client::new(
+                server.url(),
+                Secret::new("token".to_string()),
+                "owner".to_string(),
+                "repository".to_string(),
+            ),
+            3,
+            patches(),
+        )
+        .report(
+            &Indications {
+                values: vec![indication(2, 3), indication(4, 5)],
+            },
+            &Summary::default(),
+        )
+        .await
+        .unwrap();
+
+        pull_request.assert_async().await;
+        review.assert_async().await;
+        user.assert_async().await;
+        comments.assert_async().await;
+        create.assert_async().await;
+
+        // A server answering every request with an error fails the reporter.
+        let failing = mockito::Server::new_async().await;
+        let reported = GiteaReporter::new(
+            Client::new(
+                failing.url(),
+                Secret::new("token".to_string()),
+                "owner".to_string(),
+                "repository".to_string(),
+            ),
+            3,
+            patches(),
+        )
+        .report(
+            &Indications {
+                values: vec![indication(2, 3)],
+            },
+            &Summary::default(),
+        )
+        .await;
+        assert!(reported.is_err());
+    }
+}
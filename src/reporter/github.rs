@@ -1,26 +1,65 @@
+use std::collections::HashSet;
 use std::time::Duration;
 
+use super::check_failures;
 use crate::core::{Indication, Indications, Reporter};
+use crate::patches::github::login;
+use crate::summary::Summary;
+use anyhow::{anyhow, Result};
 use log::{debug, warn};
 use octocrab::Octocrab;
 use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
 use tokio::time::sleep;
 
+/// Marks the PR comment holding the summary, which is updated on every run.
+const SUMMARY_MARKER: &str = "<!-- llm-linter-summary -->";
+
+/// Marks a review comment with the fingerprint of its indication, so that the same
+/// indication is not commented on again by later runs.
+const FINGERPRINT_MARKER: &str = "<!-- llm-linter-fingerprint:";
+
 pub struct GithubReporter {
     client: Octocrab,
     owner: String,
     repository: String,
     pull_number: u64,
     token: Secret<String>,
+    commit_sha: Option<String>,
 }
 
 impl Reporter for GithubReporter {
-    async fn report(&self, indications: Indications) {
-        for indication in indications.exclude_cancel().exclude_warnings().values {
-            self.comment(indication).await;
+    async fn report(&self, indications: &Indications, summary: &Summary) -> Result<()> {
+        let mut failures = 0;
+        let commented = self.commented_fingerprints().await.unwrap_or_else(|e| {
+            warn!("Failed to read the previous review comments: {}", e);
+            failures += 1;
+            HashSet::new()
+        });
+        for indication in &indications.values {
+            if indication
+                .fingerprint
+                .as_ref()
+                .is_some_and(|fingerprint| commented.contains(fingerprint))
+            {
+                debug!("Already commented: {:?}", indication.location);
+                continue;
+            }
+            if let Err(e) = self.comment(indication).await {
+                warn!("Failed to comment: {}", e);
+                warn!(
+                    "indication: {:?}",
+                    serde_json::to_string(indication).unwrap()
+                );
+                failures += 1;
+            }
             sleep(Duration::from_secs(1)).await;
         }
+        if let Err(e) = self.summarize(summary).await {
+            warn!("Failed to post the summary: {}", e);
+            failures += 1;
+        }
+        check_failures("GitHub", failures)
     }
 }
 
@@ -42,13 +81,30 @@ impl GithubReporter {
             repository,
             pull_number,
             token: Secret::new(secret.to_string()),
+            commit_sha: None,
         }
     }
 
-    async fn comment(&self, indication: Indication) {
+    /// Comments on the given commit instead of looking up the head of the pull request.
+    pub fn with_commit_sha(mut self, commit_sha: Option<String>) -> GithubReporter {
+        self.commit_sha = commit_sha;
+        self
+    }
+
+    async fn comment(&self, indication: &Indication) -> Result<()> {
+        let body = match &indication.fingerprint {
+            Some(fingerprint) => format!(
+                "{}\n{} {} -->",
+                indication.message, FINGERPRINT_MARKER, fingerprint
+            ),
+            None => indication.message.clone(),
+        };
         let comment_request = &CommentRequest {
-            body: self.add_suffix(indication.message.clone()),
-            commit_id: self.fetch_latest_commit_sha().await,
+            body: self.add_suffix(body),
+            commit_id: match &self.commit_sha {
+                Some(sha) => sha.clone(),
+                None => self.fetch_latest_commit_sha().await?,
+            },
             path: indication.location.path.clone(),
             start_line: if indication.location.is_single_line() {
                 None
@@ -65,7 +121,7 @@ impl GithubReporter {
         };
 
         let response = reqwest::Client::new()
-            .post(&format!(
+            .post(format!(
                 "https://api.github.com/repos/{}/{}/pulls/{}/comments",
                 self.owner, self.repository, self.pull_number
             ))
@@ -76,27 +132,105 @@ impl GithubReporter {
             .header("X-GitHub-Api-Version", "2022-11-28")
             .json(comment_request)
             .send()
-            .await
-            .unwrap_or_else(|e| panic!("Failed to send request: {}", e));
+            .await?;
 
         if response.status().is_client_error() || response.status().is_server_error() {
-            warn!("GitHub API returned an error: {:?}", response.text().await);
-            warn!(
-                "indication: {:?}",
-                serde_json::to_string(&indication).unwrap()
-            );
+            return Err(anyhow!(
+                "GitHub API returned {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
         }
+        Ok(())
     }
 
-    async fn fetch_latest_commit_sha(&self) -> String {
+    /// Fingerprints of the indications commented on by previous runs. Only our own comments
+    /// are trusted, since anyone could post one with a fingerprint to silence an indication.
+    async fn commented_fingerprints(&self) -> Result<HashSet<String>> {
+        let page = self
+            .client
+            .pulls(self.owner.clone(), self.repository.clone())
+            .list_comments(Some(self.pull_number))
+            .per_page(100)
+            .send()
+            .await?;
+        let login = login(&self.client).await;
+        Ok(self
+            .client
+            .all_pages(page)
+            .await?
+            .into_iter()
+            .filter(|comment| {
+                comment
+                    .user
+                    .as_ref()
+                    .is_some_and(|user| user.login == login)
+            })
+            .filter_map(|comment| fingerprint_of(&comment.body))
+            .collect())
+    }
+
+    /// Posts the summary as a PR comment, updating the one of the previous run if any.
+    /// Only our own comment is updated, not one quoting the marker by someone else.
+    async fn summarize(&self, summary: &Summary) -> Result<()> {
+        let body = self.add_suffix(format!(
+            "{}\n## LLM linter summary\n\n{}",
+            SUMMARY_MARKER,
+            summary.to_markdown()
+        ));
+
+        let page = self
+            .client
+            .issues(self.owner.clone(), self.repository.clone())
+            .list_comments(self.pull_number)
+            .per_page(100)
+            .send()
+            .await?;
+        let login = login(&self.client).await;
+        let previous = self
+            .client
+            .all_pages(page)
+            .await?
+            .into_iter()
+            .find(|comment| {
+                comment.user.login == login
+                    && comment
+                        .body
+                        .as_ref()
+                        .is_some_and(|body| body.starts_with(SUMMARY_MARKER))
+            });
+
+        match previous {
+            Some(comment) => {
+                let route = format!(
+                    "/repos/{}/{}/issues/comments/{}",
+                    self.owner, self.repository, comment.id
+                );
+                self.client
+                    .patch::<serde_json::Value, _, _>(
+                        route,
+                        Some(&serde_json::json!({ "body": body })),
+                    )
+                    .await?;
+            }
+            None => {
+                self.client
+                    .issues(self.owner.clone(), self.repository.clone())
+                    .create_comment(self.pull_number, body)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn fetch_latest_commit_sha(&self) -> Result<String> {
         let pull = self
             .client
             .pulls(self.owner.clone(), self.repository.clone())
             .get(self.pull_number)
-            .await
-            .unwrap();
+            .await?;
         debug!("pull.head.sha: {:?}", pull.head.sha);
-        pull.head.sha
+        Ok(pull.head.sha)
     }
 
     fn add_suffix(&self, message: String) -> String {
@@ -119,3 +253,23 @@ struct CommentRequest {
     line: u64,
     side: String,
 }
+
+fn fingerprint_of(body: &str) -> Option<String> {
+    let (_, rest) = body.split_once(FINGERPRINT_MARKER)?;
+    let (fingerprint, _) = rest.split_once("-->")?;
+    Some(fingerprint.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_of() {
+        assert_eq!(
+            fingerprint_of("Use `expect`\n<!-- llm-linter-fingerprint: 0a1b -->\n\nReported by"),
+            Some("0a1b".to_string())
+        );
+        assert_eq!(fingerprint_of("Use `expect`"), None);
+    }
+}
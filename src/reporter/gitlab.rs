@@ -0,0 +1,283 @@
+use anyhow::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use super::check_failures;
+use crate::core::{Indication, Indications, Patches, Reporter};
+use crate::patches::gitlab::{Client, DiffRefs, User};
+use crate::summary::Summary;
+
+/// Marks the note holding the summary, which is updated on every run.
+const SUMMARY_MARKER: &str = "<!-- llm-linter-summary -->";
+
+/// Reports indications as discussions on the diff of a GitLab merge request.
+pub struct GitlabReporter {
+    client: Client,
+    merge_request_iid: u64,
+    /// Whole hunks of the merge request, to place the discussions on the old or new side.
+    patches: Patches,
+}
+
+impl Reporter for GitlabReporter {
+    async fn report(&self, indications: &Indications, summary: &Summary) -> Result<()> {
+        let mut failures = 0;
+        if !indications.values.is_empty() {
+            match self.client.merge_request(self.merge_request_iid).await {
+                Ok(merge_request) => {
+                    for indication in &indications.values {
+                        if let Err(e) = self.discuss(&merge_request.diff_refs, indication).await {
+                            warn!("{}", e);
+                            warn!(
+                                "indication: {:?}",
+                                serde_json::to_string(indication).unwrap()
+                            );
+                            failures += 1;
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to fetch the merge request: {}", e);
+                    failures += 1;
+                }
+            }
+        }
+        if let Err(e) = self.summarize(summary).await {
+            warn!("Failed to post the summary: {}", e);
+            failures += 1;
+        }
+        check_failures("GitLab", failures)
+    }
+}
+
+impl GitlabReporter {
+    pub fn new(client: Client, merge_request_iid: u64, patches: Patches) -> GitlabReporter {
+        GitlabReporter {
+            client,
+            merge_request_iid,
+            patches,
+        }
+    }
+
+    async fn discuss(&self, diff_refs: &DiffRefs, indication: &Indication) -> Result<()> {
+        let location = &indication.location;
+        // Like the GitHub reporter, the discussion is attached to the last line. GitLab needs
+        // the line on both sides for unchanged lines, and only the old one for removed lines.
+        let hunk = self.patches.all.iter().find_map(|patch| {
+            let sides = patch
+                .sides_of(location.end_line)
+                .filter(|_| patch.path == location.path)?;
+            Some((patch.old_path.clone(), sides))
+        });
+        let (old_path, (old_line, new_line)) =
+            hunk.unwrap_or((location.path.clone(), (None, Some(location.end_line))));
+        let request = DiscussionRequest {
+            body: add_suffix(indication.message.clone()),
+            position: Position {
+                position_type: "text".to_string(),
+                base_sha: diff_refs.base_sha.clone(),
+                start_sha: diff_refs.start_sha.clone(),
+                head_sha: diff_refs.head_sha.clone(),
+                old_path,
+                new_path: location.path.clone(),
+                old_line,
+                new_line,
+            },
+        };
+
+        Client::send(
+            self.client
+                .post(&format!(
+                    "merge_requests/{}/discussions",
+                    self.merge_request_iid
+                ))
+                .json(&request),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Posts the summary as a note, updating the one of the previous run if any.
+    /// Only our own note is updated, not one quoting the marker by someone else.
+    async fn summarize(&self, summary: &Summary) -> Result<()> {
+        let body = add_suffix(format!(
+            "{}\n## LLM linter summary\n\n{}",
+            SUMMARY_MARKER,
+            summary.to_markdown()
+        ));
+        let notes_path = format!("merge_requests/{}/notes", self.merge_request_iid);
+
+        let user = self.client.current_user().await?;
+        let previous = self
+            .client
+            .get_all::<Note>(&notes_path)
+            .await?
+            .into_iter()
+            .find(|note| {
+                note.author.username == user.username && note.body.starts_with(SUMMARY_MARKER)
+            });
+
+        let request = match previous {
+            Some(note) => self.client.put(&format!("{}/{}", notes_path, note.id)),
+            None => self.client.post(&notes_path),
+        };
+        Client::send(request.json(&serde_json::json!({ "body": body }))).await?;
+        Ok(())
+    }
+}
+
+fn add_suffix(message: String) -> String {
+    format!(
+        "{}\n\n{}",
+        message, "Reported by [LLM linter](https://github.com/HosokawaR/LLM-linter)"
+    )
+}
+
+#[derive(Debug, Serialize)]
+struct DiscussionRequest {
+    body: String,
+    position: Position,
+}
+
+/// Ref: https://docs.gitlab.com/ee/api/discussions.html#create-a-new-thread-in-the-merge-request-diff
+#[derive(Debug, Serialize)]
+struct Position {
+    position_type: String,
+    base_sha: String,
+    start_sha: String,
+    head_sha: String,
+    old_path: String,
+    new_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old_line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_line: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Note {
+    id: u64,
+    body: String,
+    author: User,
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use mockito::Matcher;
+    use secrecy::Secret;
+
+    use super::*;
+    use crate::core::IndicationKind;
+
+    #[tokio::test]
+    async fn test_report() {
+        let mut server = mockito::Server::new_async().await;
+        let merge_request = server
+            .mock("GET", "/api/v4/projects/group%2Fproject/merge_requests/7")
+            .with_body(
+                serde_json::json!({
+                    "iid": 7,
+                    "diff_refs": { "base_sha": "base", "start_sha": "start", "head_sha": "head" }
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let discussion = server
+            .mock(
+                "POST",
+                "/api/v4/projects/group%2Fproject/merge_requests/7/discussions",
+            )
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "position": {
+                    "position_type": "text",
+                    "base_sha": "base",
+                    "start_sha": "start",
+                    "head_sha": "head",
+                    "old_path": "src/old.rs",
+                    "new_path": "src/main.rs",
+                    "old_line": 4,
+                    "new_line": 4
+                }
+            })))
+            .expect(1)
+            .create_async()
+            .await;
+        let user = server
+            .mock("GET", "/api/v4/user")
+            .with_body(serde_json::json!({ "username": "linter" }).to_string())
+            .create_async()
+            .await;
+        let notes = server
+            .mock(
+                "GET",
+                "/api/v4/projects/group%2Fproject/merge_requests/7/notes",
+            )
+            .match_query(Matcher::Any)
+            .with_body(
+                // Only the summary by the linter itself is updated, not one quoting it.
+                serde_json::json!([
+                    { "id": 1, "body": "LGTM", "author": { "username": "alice" } },
+                    {
+                        "id": 2,
+                        "body": format!("{}\n## LLM linter summary", SUMMARY_MARKER),
+                        "author": { "username": "alice" }
+                    },
+                    {
+                        "id": 3,
+                        "body": format!("{}\n## LLM linter summary", SUMMARY_MARKER),
+                        "author": { "username": "linter" }
+                    }
+                ])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let update = server
+            .mock(
+                "PUT",
+                "/api/v4/projects/group%2Fproject/merge_requests/7/notes/3",
+            )
+            .create_async()
+            .await;
+
+        let indication = |kind| Indication {
+            kind,
+            message: "Use `expect`".to_string(),
+            ..Indication::for_test("src/main.rs", 3, 5)
+        };
+        GitlabReporter::new(
+            Client::new(
+                server.url(),
+                Secret::new("token".to_string()),
+                "group/project".to_string(),
+            ),
+            7,
+            // A renamed file, with the indication ending on an unchanged line.
+            Patches::parse(indoc! {"
+                --- a/src/old.rs
+                +++ b/src/main.rs
+                @@ -2,3 +2,3 @@
+                 fn main() {
+                -    a();
+                +    b();
+                 }
+            "})
+            .unwrap(),
+        )
+        .report(
+            &Indications {
+                values: vec![indication(IndicationKind::Error)],
+            },
+            &Summary::default(),
+        )
+        .await
+        .unwrap();
+
+        merge_request.assert_async().await;
+        discussion.assert_async().await;
+        user.assert_async().await;
+        notes.assert_async().await;
+        update.assert_async().await;
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::{escape_xml, write_output};
+use crate::core::{Indication, IndicationKind, Indications, LineKind, Patches, Reporter};
+use crate::summary::Summary;
+
+/// Writes a single static HTML file with the indications grouped by file and rule, the
+/// hunks around them, the reasoning of the model and the usage, e.g. as a CI artifact.
+pub struct HtmlReporter {
+    /// Prints to stdout if `None`.
+    output: Option<PathBuf>,
+    /// Hunks the indications point to, to show the lines around them.
+    patches: Patches,
+}
+
+/// Lines of the hunk shown around the flagged ones.
+const CONTEXT_LINES: u64 = 3;
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #1f2328; }
+table { border-collapse: collapse; }
+td, th { border: 1px solid #d0d7de; padding: 0.2em 0.6em; text-align: left; vertical-align: top; }
+.indication { border-left: 4px solid #d0d7de; margin: 1em 0; padding: 0 1em; }
+.error { border-color: #cf222e; }
+.warning { border-color: #bf8700; }
+.info, .hint { border-color: #0969da; }
+.cancel { border-color: #8c959f; }
+.diff { font-family: monospace; white-space: pre; width: 100%; }
+.diff td { border: none; width: 50%; }
+.diff .number { width: 1%; color: #8c959f; }
+.removed { background: #ffebe9; }
+.added { background: #dafbe1; }
+.flagged td { outline: 1px solid #bf8700; }
+pre { background: #f6f8fa; padding: 0.6em; }
+";
+
+impl Reporter for HtmlReporter {
+    async fn report(&self, indications: &Indications, summary: &Summary) -> Result<()> {
+        write_output(&self.output, &self.html(indications, summary))
+    }
+}
+
+impl HtmlReporter {
+    pub fn new(output: Option<PathBuf>, patches: Patches) -> HtmlReporter {
+        HtmlReporter { output, patches }
+    }
+
+    fn html(&self, indications: &Indications, summary: &Summary) -> String {
+        let (cancelled, reported): (Vec<&Indication>, Vec<&Indication>) = indications
+            .values
+            .iter()
+            .partition(|indication| indication.kind == IndicationKind::Cancel);
+
+        let mut lines = vec![
+            "<!DOCTYPE html>".to_string(),
+            "<html>".to_string(),
+            "<head>".to_string(),
+            r#"<meta charset="utf-8">"#.to_string(),
+            "<title>LLM linter report</title>".to_string(),
+            format!("<style>{}</style>", STYLE),
+            "</head>".to_string(),
+            "<body>".to_string(),
+            "<h1>LLM linter report</h1>".to_string(),
+            r#"<table class="summary">"#.to_string(),
+        ];
+        for (label, value) in summary.rows() {
+            lines.push(format!(
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape_xml(&label),
+                escape_xml(&value)
+            ));
+        }
+        lines.push("</table>".to_string());
+
+        if reported.is_empty() {
+            lines.push("<p>No indications.</p>".to_string());
+        }
+        lines.extend(self.grouped(&reported, "h2", "h3"));
+
+        if !cancelled.is_empty() {
+            lines.push("<details>".to_string());
+            lines.push(format!(
+                "<summary>Cancelled indications ({})</summary>",
+                cancelled.len()
+            ));
+            lines.extend(self.grouped(&cancelled, "h3", "h4"));
+            lines.push("</details>".to_string());
+        }
+
+        lines.push("</body>".to_string());
+        lines.push("</html>".to_string());
+        lines.join("\n")
+    }
+
+    /// Renders the indications under a heading per file and a heading per rule.
+    fn grouped(&self, indications: &[&Indication], file_tag: &str, rule_tag: &str) -> Vec<String> {
+        let mut files: BTreeMap<&str, BTreeMap<&str, Vec<&Indication>>> = BTreeMap::new();
+        for indication in indications {
+            files
+                .entry(&indication.location.path)
+                .or_default()
+                .entry(indication.rule.as_deref().unwrap_or("unknown rule"))
+                .or_default()
+                .push(indication);
+        }
+
+        let mut lines = Vec::new();
+        for (path, rules) in files {
+            lines.push(format!("<{0}>{1}</{0}>", file_tag, escape_xml(path)));
+            for (rule, indications) in rules {
+                lines.push(format!("<{0}>{1}</{0}>", rule_tag, escape_xml(rule)));
+                for indication in indications {
+                    lines.extend(self.indication(indication));
+                }
+            }
+        }
+        lines
+    }
+
+    fn indication(&self, indication: &Indication) -> Vec<String> {
+        let location = &indication.location;
+        let kind = format!("{:?}", indication.kind).to_lowercase();
+        let mut lines = vec![
+            format!(r#"<div class="indication {}">"#, kind),
+            format!(
+                "<p><strong>{}</strong> at lines {}-{}: {}</p>",
+                kind,
+                location.start_line,
+                location.end_line,
+                escape_xml(&indication.message)
+            ),
+        ];
+        lines.extend(self.side_by_side(indication));
+        if let Some(suggestion) = &indication.suggestion {
+            lines.push("<p>Suggestion:</p>".to_string());
+            lines.push(format!("<pre>{}</pre>", escape_xml(suggestion)));
+        }
+        if let Some(reasoning) = &indication.reasoning {
+            lines.push("<details>".to_string());
+            lines.push("<summary>Reasoning</summary>".to_string());
+            lines.push("<dl>".to_string());
+            for (term, description) in [
+                ("Quotation", &reasoning.quotation),
+                ("Rule", &reasoning.reference),
+                ("Why it is suspicious", &reasoning.suspicious_reason),
+                ("Why it may be fine", &reasoning.no_problem_reason),
+                ("Reevaluation", &reasoning.reevaluation),
+            ] {
+                lines.push(format!(
+                    "<dt>{}</dt><dd>{}</dd>",
+                    term,
+                    escape_xml(description)
+                ));
+            }
+            lines.push("</dl>".to_string());
+            lines.push("</details>".to_string());
+        }
+        lines.push("</div>".to_string());
+        lines
+    }
+
+    /// Shows the removed lines of the hunk on the left and the added ones on the right,
+    /// with the numbers indications refer to.
+    fn side_by_side(&self, indication: &Indication) -> Vec<String> {
+        let location = &indication.location;
+        let Some(patch) = self.patches.all.iter().find(|patch| {
+            patch.path == location.path
+                && patch.start_line <= location.end_line
+                && location.start_line <= patch.end_line
+        }) else {
+            return Vec::new();
+        };
+        let first = location.start_line.saturating_sub(CONTEXT_LINES);
+        let last = location.end_line + CONTEXT_LINES;
+        let lines = patch
+            .diff_lines()
+            .into_iter()
+            .filter(|(number, _, _)| first <= *number && *number <= last);
+
+        // Removed lines wait for the added lines replacing them, to be shown on the same row.
+        let mut rows = Vec::new();
+        let mut removed = Vec::new();
+        let changed = |left, right| Row {
+            left,
+            right,
+            context: false,
+        };
+        for (number, kind, text) in lines {
+            match kind {
+                LineKind::Removed => removed.push((number, text)),
+                LineKind::Added => {
+                    let left = (!removed.is_empty()).then(|| removed.remove(0));
+                    rows.push(changed(left, Some((number, text))));
+                }
+                LineKind::Context => {
+                    rows.extend(removed.drain(..).map(|line| changed(Some(line), None)));
+                    rows.push(Row {
+                        left: Some((number, text.clone())),
+                        right: Some((number, text)),
+                        context: true,
+                    });
+                }
+            }
+        }
+        rows.extend(removed.drain(..).map(|line| changed(Some(line), None)));
+
+        let flagged = |line: &Option<(u64, String)>| {
+            line.as_ref().is_some_and(|(number, _)| {
+                location.start_line <= *number && *number <= location.end_line
+            })
+        };
+        let mut html = vec![r#"<table class="diff">"#.to_string()];
+        for Row {
+            left,
+            right,
+            context,
+        } in rows
+        {
+            let cell = |line: &Option<(u64, String)>, class: &str| match line {
+                Some((_, text)) if context => format!("<td>{}</td>", escape_xml(text)),
+                Some((_, text)) => format!(r#"<td class="{}">{}</td>"#, class, escape_xml(text)),
+                None => "<td></td>".to_string(),
+            };
+            html.push(format!(
+                r#"<tr{}><td class="number">{}</td>{}{}</tr>"#,
+                if flagged(&left) || flagged(&right) {
+                    r#" class="flagged""#
+                } else {
+                    ""
+                },
+                right
+                    .as_ref()
+                    .or(left.as_ref())
+                    .map_or(String::new(), |(number, _)| number.to_string()),
+                cell(&left, "removed"),
+                cell(&right, "added")
+            ));
+        }
+        html.push("</table>".to_string());
+        html
+    }
+}
+
+/// A row of the side-by-side diff, with the numbered lines of each side.
+struct Row {
+    left: Option<(u64, String)>,
+    right: Option<(u64, String)>,
+    context: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::core::Reasoning;
+
+    #[test]
+    fn test_html() {
+        let patches = Patches::parse(indoc! {"
+            --- a/src/main.rs
+            +++ b/src/main.rs
+            @@ -1,3 +1,3 @@
+             fn main() {
+            -    let x = y.expect(\"y\");
+            +    let x = y.unwrap();
+             }
+        "})
+        .unwrap();
+        let indication = |kind, rule: &str| Indication {
+            kind,
+            message: "Use <expect>".to_string(),
+            rule: Some(rule.to_string()),
+            reasoning: Some(Reasoning {
+                quotation: "y.unwrap()".to_string(),
+                ..Default::default()
+            }),
+            ..Indication::for_test("src/main.rs", 3, 3)
+        };
+        let indications = Indications {
+            values: vec![
+                indication(IndicationKind::Error, "for-rust"),
+                indication(IndicationKind::Cancel, "naming"),
+            ],
+        };
+
+        let html = HtmlReporter::new(None, patches).html(&indications, &Summary::default());
+
+        assert!(html.contains("<h2>src/main.rs</h2>\n<h3>for-rust</h3>"));
+        assert!(html.contains("<strong>error</strong> at lines 3-3: Use &lt;expect&gt;"));
+        assert!(html.contains(concat!(
+            r#"<tr class="flagged"><td class="number">3</td>"#,
+            r#"<td class="removed">    let x = y.expect(&quot;y&quot;);</td>"#,
+            r#"<td class="added">    let x = y.unwrap();</td></tr>"#
+        )));
+        assert!(html.contains("<dt>Quotation</dt><dd>y.unwrap()</dd>"));
+        assert!(html.contains(
+            "<summary>Cancelled indications (1)</summary>\n<h3>src/main.rs</h3>\n<h4>naming</h4>"
+        ));
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::{escape_xml, write_output};
+use crate::core::{Indication, IndicationKind, Indications, Patches, Reporter, Rules};
+use crate::summary::Summary;
+
+/// Writes JUnit XML with a test case per rule and linted file, failing on errors, for the
+/// test report views of Jenkins and GitLab.
+pub struct JunitReporter {
+    /// Prints to stdout if `None`.
+    output: Option<PathBuf>,
+    /// Pairs of a linted file and a rule matching it, which pass unless indicated.
+    cases: Vec<(String, String)>,
+}
+
+impl Reporter for JunitReporter {
+    async fn report(&self, indications: &Indications, _summary: &Summary) -> Result<()> {
+        write_output(&self.output, &self.junit(indications))
+    }
+}
+
+impl JunitReporter {
+    pub fn new(output: Option<PathBuf>, rules: &Rules, linted: &Patches) -> JunitReporter {
+        let mut cases = Vec::new();
+        for patch in &linted.all {
+            for rule in rules.all.iter().filter(|rule| rule.matches(&patch.path)) {
+                cases.push((patch.path.clone(), rule.name.clone()));
+            }
+        }
+        JunitReporter { output, cases }
+    }
+
+    fn junit(&self, indications: &Indications) -> String {
+        let mut cases: BTreeMap<(&str, &str), Vec<&Indication>> = self
+            .cases
+            .iter()
+            .map(|(path, rule)| ((path.as_str(), rule.as_str()), Vec::new()))
+            .collect();
+        for indication in &indications.values {
+            let rule = indication.rule.as_deref().unwrap_or("unknown");
+            cases
+                .entry((&indication.location.path, rule))
+                .or_default()
+                .push(indication);
+        }
+
+        let failures = cases
+            .values()
+            .filter(|indications| has_error(indications))
+            .count();
+        let mut lines = vec![
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+            format!(
+                r#"<testsuite name="llm-linter" tests="{}" failures="{}" errors="0">"#,
+                cases.len(),
+                failures
+            ),
+        ];
+        for ((path, rule), indications) in cases {
+            let attributes = format!(
+                r#"classname="{}" name="{}""#,
+                escape_xml(path),
+                escape_xml(rule)
+            );
+            if indications.is_empty() {
+                lines.push(format!("  <testcase {}/>", attributes));
+                continue;
+            }
+
+            lines.push(format!("  <testcase {}>", attributes));
+            let details = indications
+                .iter()
+                .map(|indication| {
+                    format!(
+                        "{:?} at lines {}-{}: {}",
+                        indication.kind,
+                        indication.location.start_line,
+                        indication.location.end_line,
+                        indication.message
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
+            // Warnings alone do not fail the case, but are still shown in its output.
+            if has_error(&indications) {
+                lines.push(format!(
+                    r#"    <failure message="{}" type="error">{}</failure>"#,
+                    escape_xml(&indications[0].message),
+                    escape_xml(&details)
+                ));
+            } else {
+                lines.push(format!(
+                    "    <system-out>{}</system-out>",
+                    escape_xml(&details)
+                ));
+            }
+            lines.push("  </testcase>".to_string());
+        }
+        lines.push("</testsuite>".to_string());
+        lines.join("\n")
+    }
+}
+
+fn has_error(indications: &[&Indication]) -> bool {
+    indications
+        .iter()
+        .any(|indication| indication.kind == IndicationKind::Error)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::core::Rule;
+
+    #[test]
+    fn test_junit() {
+        let rules = Rules {
+            all: vec![
+                Rule {
+                    name: "for-rust".to_string(),
+                    target_file_glob: "**/*.rs".to_string(),
+                    content: String::new(),
+                    severity: None,
+                },
+                Rule {
+                    name: "for-markdown".to_string(),
+                    target_file_glob: "**/*.md".to_string(),
+                    content: String::new(),
+                    severity: None,
+                },
+            ],
+        };
+        let linted = Patches::parse(indoc! {"
+            --- a/src/main.rs
+            +++ b/src/main.rs
+            @@ -1,2 +1,3 @@
+             fn main() {
+            +    run();
+             }
+            --- a/src/lib.rs
+            +++ b/src/lib.rs
+            @@ -1,1 +1,2 @@
+             mod core;
+            +mod cache;
+        "})
+        .unwrap();
+        let indication = |kind, message: &str| Indication {
+            kind,
+            message: message.to_string(),
+            rule: Some("for-rust".to_string()),
+            ..Indication::for_test("src/main.rs", 2, 2)
+        };
+        let indications = Indications {
+            values: vec![
+                indication(IndicationKind::Error, "Handle <errors>"),
+                indication(IndicationKind::Warning, "Name it"),
+            ],
+        };
+
+        assert_eq!(
+            JunitReporter::new(None, &rules, &linted).junit(&indications),
+            indoc! {r#"
+                <?xml version="1.0" encoding="UTF-8"?>
+                <testsuite name="llm-linter" tests="2" failures="1" errors="0">
+                  <testcase classname="src/lib.rs" name="for-rust"/>
+                  <testcase classname="src/main.rs" name="for-rust">
+                    <failure message="Handle &lt;errors&gt;" type="error">Error at lines 2-2: Handle &lt;errors&gt;
+                Warning at lines 2-2: Name it</failure>
+                  </testcase>
+                </testsuite>"#}
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::write_output;
+use crate::core::{Indication, IndicationKind, Indications, Reporter};
+use crate::summary::Summary;
+
+/// Writes the Reviewdog Diagnostic Format, for reviewdog to post to any code host with
+/// `reviewdog -f=rdjson` or `reviewdog -f=rdjsonl`.
+/// Ref: https://github.com/reviewdog/reviewdog/tree/master/proto/rdf
+pub struct RdjsonReporter {
+    /// Prints to stdout if `None`.
+    output: Option<PathBuf>,
+    /// Writes one diagnostic per line (rdjsonl) instead of a single result (rdjson).
+    lines: bool,
+}
+
+impl Reporter for RdjsonReporter {
+    async fn report(&self, indications: &Indications, _summary: &Summary) -> Result<()> {
+        let diagnostics = indications
+            .values
+            .iter()
+            .map(diagnostic)
+            .collect::<Vec<Diagnostic>>();
+
+        let report = if self.lines {
+            diagnostics
+                .iter()
+                .map(|diagnostic| serde_json::to_string(diagnostic).unwrap())
+                .collect::<Vec<String>>()
+                .join("\n")
+        } else {
+            serde_json::to_string_pretty(&DiagnosticResult {
+                source: source(),
+                diagnostics,
+            })
+            .unwrap()
+        };
+        write_output(&self.output, &report)
+    }
+}
+
+impl RdjsonReporter {
+    pub fn new(output: Option<PathBuf>, lines: bool) -> RdjsonReporter {
+        RdjsonReporter { output, lines }
+    }
+}
+
+fn source() -> Source {
+    Source {
+        name: "llm-linter".to_string(),
+        url: "https://github.com/HosokawaR/LLM-linter".to_string(),
+    }
+}
+
+fn diagnostic(indication: &Indication) -> Diagnostic {
+    let location = &indication.location;
+    Diagnostic {
+        message: indication.message.clone(),
+        location: DiagnosticLocation {
+            path: location.path.clone(),
+            range: Range {
+                start: Position {
+                    line: location.start_line,
+                    column: None,
+                },
+                end: Position {
+                    line: location.end_line,
+                    column: None,
+                },
+            },
+        },
+        severity: match indication.kind {
+            IndicationKind::Error => "ERROR",
+            IndicationKind::Warning | IndicationKind::Cancel => "WARNING",
+            IndicationKind::Info | IndicationKind::Hint => "INFO",
+        }
+        .to_string(),
+        source: source(),
+        code: indication.rule.as_ref().map(|rule| Code {
+            value: rule.clone(),
+        }),
+        // A suggestion replaces the lines as a whole, so it ends at the start of the next line.
+        suggestions: indication
+            .suggestion
+            .iter()
+            .map(|suggestion| Suggestion {
+                range: Range {
+                    start: Position {
+                        line: location.start_line,
+                        column: Some(1),
+                    },
+                    end: Position {
+                        line: location.end_line + 1,
+                        column: Some(1),
+                    },
+                },
+                text: format!("{}\n", suggestion.trim_end_matches('\n')),
+            })
+            .collect(),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct DiagnosticResult {
+    source: Source,
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Serialize)]
+struct Diagnostic {
+    message: String,
+    location: DiagnosticLocation,
+    severity: String,
+    source: Source,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<Code>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suggestions: Vec<Suggestion>,
+}
+
+#[derive(Debug, Serialize)]
+struct Source {
+    name: String,
+    url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DiagnosticLocation {
+    path: String,
+    range: Range,
+}
+
+#[derive(Debug, Serialize)]
+struct Range {
+    start: Position,
+    end: Position,
+}
+
+/// Lines and columns start at 1.
+#[derive(Debug, Serialize)]
+struct Position {
+    line: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Code {
+    value: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Suggestion {
+    range: Range,
+    text: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostic() {
+        let indication = Indication {
+            kind: IndicationKind::Warning,
+            message: "Use `expect`".to_string(),
+            rule: Some("for-rust".to_string()),
+            suggestion: Some("    let x = y\n        .expect(\"y\");".to_string()),
+            ..Indication::for_test("src/main.rs", 3, 4)
+        };
+
+        assert_eq!(
+            serde_json::to_value(diagnostic(&indication)).unwrap(),
+            serde_json::json!({
+                "message": "Use `expect`",
+                "location": {
+                    "path": "src/main.rs",
+                    "range": { "start": { "line": 3 }, "end": { "line": 4 } }
+                },
+                "severity": "WARNING",
+                "source": { "name": "llm-linter", "url": "https://github.com/HosokawaR/LLM-linter" },
+                "code": { "value": "for-rust" },
+                "suggestions": [{
+                    "range": {
+                        "start": { "line": 3, "column": 1 },
+                        "end": { "line": 5, "column": 1 }
+                    },
+                    "text": "    let x = y\n        .expect(\"y\");\n"
+                }]
+            })
+        );
+    }
+}
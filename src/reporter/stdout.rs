@@ -1,24 +1,281 @@
-use crate::core::{Indications, Reporter};
+use std::env;
+use std::io::{stdout, IsTerminal};
 
-pub struct StdoutReporter {}
+use anyhow::Result;
+use unicode_width::UnicodeWidthStr;
+
+use crate::core::{Indication, IndicationKind, Indications, Patches, Reporter};
+use crate::summary::Summary;
+
+/// Prints indications like compiler diagnostics, with the flagged lines of the hunks.
+pub struct StdoutReporter {
+    /// Hunks the indications point to, to show the flagged lines from.
+    patches: Patches,
+    color: bool,
+}
+
+/// Flagged lines shown at most per indication; the rest is elided.
+const MAX_EXCERPT_LINES: usize = 10;
+
+/// Lines shown around the flagged ones.
+const CONTEXT_LINES: u64 = 1;
+
+const RED: &str = "31";
+const YELLOW: &str = "33";
+const BLUE: &str = "34";
+const CYAN: &str = "36";
+const BOLD: &str = "1";
 
 impl Reporter for StdoutReporter {
-    async fn report(&self, indications: Indications) {
-        for indication in indications.exclude_cancel().exclude_warnings().values {
-            println!(
-                "{}\nfrom {} to {}\nkind: {:?}\n{}\n",
-                indication.location.path,
-                indication.location.start_line,
-                indication.location.end_line,
-                indication.kind,
-                indication.message
-            );
-        }
+    async fn report(&self, indications: &Indications, summary: &Summary) -> Result<()> {
+        self.print_indications(indications);
+        println!("{}", summary);
+        Ok(())
     }
 }
 
 impl StdoutReporter {
-    pub fn new() -> StdoutReporter {
-        StdoutReporter {}
+    /// Colors are used only on a terminal and unless `NO_COLOR` is set.
+    /// Ref: https://no-color.org/
+    pub fn new(patches: Patches) -> StdoutReporter {
+        StdoutReporter {
+            patches,
+            color: stdout().is_terminal()
+                && env::var("NO_COLOR").map_or(true, |value| value.is_empty()),
+        }
+    }
+
+    pub fn print_indications(&self, indications: &Indications) {
+        for indication in &indications.values {
+            println!("{}\n", self.render(indication));
+        }
+
+        println!("{}", self.paint(BOLD, &tally(indications)));
+    }
+
+    fn render(&self, indication: &Indication) -> String {
+        let location = &indication.location;
+        let (severity, color) = match indication.kind {
+            IndicationKind::Error => ("error", RED),
+            IndicationKind::Warning => ("warning", YELLOW),
+            IndicationKind::Info => ("info", CYAN),
+            IndicationKind::Hint => ("hint", CYAN),
+            IndicationKind::Cancel => ("cancelled", BLUE),
+        };
+        let header = match &indication.rule {
+            Some(rule) => format!("{}[{}]", severity, rule),
+            None => severity.to_string(),
+        };
+        let lines = self.excerpt(indication);
+        let width = lines
+            .iter()
+            .map(|(number, _)| number.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let gutter = |number: &str| self.paint(BLUE, &format!("{:>width$} |", number));
+
+        let mut output = vec![
+            format!(
+                "{}{}",
+                self.paint(color, &self.paint(BOLD, &header)),
+                self.paint(BOLD, &format!(": {}", indication.message))
+            ),
+            format!(
+                "{}{} {}:{}",
+                " ".repeat(width),
+                self.paint(BLUE, "-->"),
+                location.path,
+                if location.is_single_line() {
+                    location.start_line.to_string()
+                } else {
+                    format!("{}-{}", location.start_line, location.end_line)
+                }
+            ),
+        ];
+        if !lines.is_empty() {
+            output.push(gutter(""));
+        }
+        let mut flagged = 0;
+        for (number, text) in &lines {
+            let is_flagged = location.start_line <= *number && *number <= location.end_line;
+            if is_flagged {
+                flagged += 1;
+                if flagged > MAX_EXCERPT_LINES {
+                    continue;
+                }
+            }
+            output.push(format!("{} {}", gutter(&number.to_string()), text));
+            if is_flagged {
+                // Underlines the code, without the marker of the diff and the indentation,
+                // in columns of the terminal so that wide characters are covered.
+                let code = text.strip_prefix(['+', '-', ' ']).unwrap_or(text);
+                let indent = text.width() - code.trim_start().width();
+                let underline = "^".repeat(code.trim().width().max(1));
+                output.push(format!(
+                    "{} {}{}",
+                    gutter(""),
+                    " ".repeat(indent),
+                    self.paint(color, &underline)
+                ));
+            }
+        }
+        if flagged > MAX_EXCERPT_LINES {
+            output.push(format!(
+                "{} ... {} more lines",
+                gutter(""),
+                flagged - MAX_EXCERPT_LINES
+            ));
+        }
+        if let Some(suggestion) = &indication.suggestion {
+            output.push(format!(
+                "{} {}",
+                self.paint(BLUE, &format!("{:>width$} =", "")),
+                self.paint(CYAN, "suggestion:")
+            ));
+            for line in suggestion.lines() {
+                output.push(format!("{} {}", gutter(""), self.paint(CYAN, line)));
+            }
+        }
+        output.join("\n")
+    }
+
+    /// Numbered lines of the hunk around the location, as they were shown to the model.
+    fn excerpt(&self, indication: &Indication) -> Vec<(u64, String)> {
+        let location = &indication.location;
+        let first = location.start_line.saturating_sub(CONTEXT_LINES);
+        let last = location.end_line + CONTEXT_LINES;
+        let Some(patch) = self.patches.all.iter().find(|patch| {
+            patch.path == location.path
+                && patch.start_line <= location.end_line
+                && location.start_line <= patch.end_line
+        }) else {
+            return Vec::new();
+        };
+        patch
+            .numbered_lines()
+            .into_iter()
+            .filter(|(number, _)| first <= *number && *number <= last)
+            .collect()
+    }
+
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Counts of the indications by kind, e.g. `1 error, 2 warnings, 1 hint`. Errors and
+/// warnings are always counted, the other kinds only when there are any.
+fn tally(indications: &Indications) -> String {
+    [
+        (IndicationKind::Error, "error", "errors"),
+        (IndicationKind::Warning, "warning", "warnings"),
+        (IndicationKind::Info, "info", "infos"),
+        (IndicationKind::Hint, "hint", "hints"),
+        (IndicationKind::Cancel, "cancelled", "cancelled"),
+    ]
+    .into_iter()
+    .filter_map(|(kind, singular, plural)| {
+        let count = indications
+            .values
+            .iter()
+            .filter(|indication| indication.kind == kind)
+            .count();
+        let always = matches!(kind, IndicationKind::Error | IndicationKind::Warning);
+        (count > 0 || always)
+            .then(|| format!("{} {}", count, if count == 1 { singular } else { plural }))
+    })
+    .collect::<Vec<String>>()
+    .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let patches = Patches::parse(indoc! {"
+            --- a/src/main.rs
+            +++ b/src/main.rs
+            @@ -1,3 +1,4 @@
+             fn main() {
+            +    let x = y.unwrap();
+                 run(x);
+             }
+        "})
+        .unwrap();
+        let reporter = StdoutReporter {
+            patches,
+            color: false,
+        };
+        let indication = Indication {
+            message: "Use `expect`".to_string(),
+            rule: Some("for-rust".to_string()),
+            suggestion: Some("+    let x = y.expect(\"y is set\");".to_string()),
+            ..Indication::for_test("src/main.rs", 2, 2)
+        };
+
+        assert_eq!(
+            reporter.render(&indication),
+            indoc! {r#"
+                error[for-rust]: Use `expect`
+                 --> src/main.rs:2
+                  |
+                1 |  fn main() {
+                2 | +    let x = y.unwrap();
+                  |      ^^^^^^^^^^^^^^^^^^^
+                3 |      run(x);
+                  = suggestion:
+                  | +    let x = y.expect("y is set");"#}
+        );
+
+        // Wide characters take two columns.
+        let reporter = StdoutReporter {
+            patches: Patches::parse(indoc! {r#"
+                --- a/src/main.rs
+                +++ b/src/main.rs
+                @@ -1,1 +1,1 @@
+                -    println!("hello");
+                +    println!("こんにちは");
+            "#})
+            .unwrap(),
+            color: false,
+        };
+        let underline = reporter
+            .render(&Indication {
+                suggestion: None,
+                ..indication.clone()
+            })
+            .lines()
+            .nth(5)
+            .unwrap()
+            .to_string();
+        assert_eq!(underline, format!("  |      {}", "^".repeat(23)));
+
+        let kinds = [
+            IndicationKind::Warning,
+            IndicationKind::Hint,
+            IndicationKind::Hint,
+            IndicationKind::Cancel,
+        ];
+        let indications = Indications {
+            values: kinds
+                .into_iter()
+                .map(|kind| Indication {
+                    kind,
+                    ..indication.clone()
+                })
+                .collect(),
+        };
+        assert_eq!(
+            tally(&indications),
+            "0 errors, 1 warning, 2 hints, 1 cancelled"
+        );
     }
 }
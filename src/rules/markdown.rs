@@ -1,4 +1,4 @@
-use crate::core::{Rule, Rules};
+use crate::core::{IndicationKind, Rule, RuleSeverity, Rules};
 use regex::Regex;
 use std::fs::read_to_string;
 
@@ -9,10 +9,19 @@ pub fn read(path: &str) -> Rules {
 
 fn parse(raw: &str) -> Rules {
     let mut rules_by_globs: Vec<(String, String)> = Vec::new();
+    let mut severities: Vec<(String, RuleSeverity)> = Vec::new();
 
     let re = Regex::new(r"<!--\s*llm-lint-glob: (.*?)\s*-->").unwrap();
-    let mut current_glob = None;
+    let severity_re = Regex::new(r"<!--\s*llm-lint-severity: (.*?)\s*-->").unwrap();
+    let mut current_glob: Option<&str> = None;
     for line in raw.split('\n') {
+        if let Some(c) = severity_re.captures(line) {
+            let glob =
+                current_glob.unwrap_or_else(|| panic!("Severity given before any glob: {}", line));
+            severities.retain(|(g, _)| g != glob);
+            severities.push((glob.to_string(), parse_severity(&c[1])));
+            continue;
+        }
         match re.captures(line) {
             Some(c) => {
                 let globs = c
@@ -35,14 +44,58 @@ fn parse(raw: &str) -> Rules {
         }
     }
 
-    Rules {
-        all: rules_by_globs
-            .into_iter()
-            .map(|(glob, rules)| Rule {
-                target_file_glob: glob,
-                content: rules,
-            })
-            .collect::<Vec<Rule>>(),
+    let all = rules_by_globs
+        .into_iter()
+        .map(|(glob, rules)| Rule {
+            name: name_of(&glob, &rules),
+            severity: severities
+                .iter()
+                .find(|(g, _)| g == &glob)
+                .map(|(_, severity)| *severity),
+            target_file_glob: glob,
+            content: rules,
+        })
+        .collect::<Vec<Rule>>();
+    // Names identify rules in indications, suppressions and fixtures, so they must be unique.
+    for (i, rule) in all.iter().enumerate() {
+        if let Some(other) = all[..i].iter().find(|other| other.name == rule.name) {
+            panic!(
+                "Rules for {} and {} are both named {}, give them distinct headings",
+                other.target_file_glob, rule.target_file_glob, rule.name
+            );
+        }
+    }
+    Rules { all }
+}
+
+/// Parses `KIND`, replacing the kind chosen by the model, or `max KIND`, lowering it.
+fn parse_severity(value: &str) -> RuleSeverity {
+    let parse = |kind| {
+        IndicationKind::parse(kind).unwrap_or_else(|e| panic!("Could not parse severity: {}", e))
+    };
+    match value.strip_prefix("max ") {
+        Some(kind) => RuleSeverity::Cap(parse(kind.trim())),
+        None => RuleSeverity::Override(parse(value)),
+    }
+}
+
+/// Derives a rule name from the first heading of the rule, falling back to the glob.
+fn name_of(glob: &str, content: &str) -> String {
+    let heading = content
+        .lines()
+        .find(|line| line.starts_with('#'))
+        .map(|line| line.trim_start_matches('#').trim());
+    let slug = heading
+        .unwrap_or(glob)
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-");
+    if slug.is_empty() {
+        glob.to_string()
+    } else {
+        slug
     }
 }
 
@@ -64,6 +117,7 @@ mod tests {
             - Do not use `expect` in production code
 
             <!-- llm-lint-glob: src/**/*.md -->
+            <!-- llm-lint-severity: max info -->
             ## for Markdown
 
             - Must have a title
@@ -73,7 +127,9 @@ mod tests {
         let rules = parse(markdown);
 
         assert_eq!(rules.all.len(), 2);
+        assert_eq!(rules.all[0].name, "for-rust");
         assert_eq!(rules.all[0].target_file_glob, "src/**/*.rs");
+        assert_eq!(rules.all[0].severity, None);
         assert_eq!(
             rules.all[0].content,
             indoc! {"
@@ -83,7 +139,12 @@ mod tests {
                 - Do not use `expect` in production code
             "}
         );
+        assert_eq!(rules.all[1].name, "for-markdown");
         assert_eq!(rules.all[1].target_file_glob, "src/**/*.md");
+        assert_eq!(
+            rules.all[1].severity,
+            Some(RuleSeverity::Cap(IndicationKind::Info))
+        );
         assert_eq!(
             rules.all[1].content,
             indoc! {"
@@ -94,4 +155,16 @@ mod tests {
             "}
         );
     }
+
+    #[test]
+    #[should_panic(expected = "both named naming")]
+    fn test_parse_duplicate_names() {
+        parse(indoc! {"
+            <!-- llm-lint-glob: src/**/*.rs -->
+            ## Naming
+
+            <!-- llm-lint-glob: src/**/*.ts -->
+            ## Naming!
+        "});
+    }
 }
@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::core::{Indications, Usage};
+
+/// Aggregated statistics of a run, available to every reporter.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Summary {
+    pub hunks_linted: usize,
+    pub hunks_skipped: usize,
+    pub cache_hits: usize,
+    pub requests: usize,
+    pub retries: u32,
+    pub currency: String,
+    pub models: BTreeMap<String, ModelUsage>,
+    pub latency_p50_ms: Option<u128>,
+    pub latency_p95_ms: Option<u128>,
+    /// Number of indications by kind, including cancelled ones.
+    pub indications: BTreeMap<String, usize>,
+    /// Indications dropped since too few answers of the model agreed on them.
+    pub outvoted: usize,
+    #[serde(skip)]
+    latencies: Vec<Duration>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ModelUsage {
+    pub requests: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// `None` if the model is missing in the pricing.
+    pub cost: Option<f64>,
+}
+
+impl Summary {
+    pub fn record_request(
+        &mut self,
+        model: &str,
+        usage: &Usage,
+        cost: Option<f64>,
+        retries: u32,
+        latency: Duration,
+    ) {
+        self.requests += 1;
+        self.retries += retries;
+        self.latencies.push(latency);
+
+        let model_usage = self.models.entry(model.to_string()).or_insert(ModelUsage {
+            cost: Some(0.0),
+            ..Default::default()
+        });
+        model_usage.requests += 1;
+        model_usage.prompt_tokens += usage.prompt_tokens;
+        model_usage.completion_tokens += usage.completion_tokens;
+        model_usage.cost = model_usage.cost.zip(cost).map(|(a, b)| a + b);
+    }
+
+    /// Total cost of the models with a known price.
+    pub fn cost(&self) -> f64 {
+        self.models.values().filter_map(|usage| usage.cost).sum()
+    }
+
+    /// Fills in the figures only known at the end of the run.
+    pub fn finish(mut self, currency: &str, indications: &Indications) -> Summary {
+        self.currency = currency.to_string();
+        self.latencies.sort();
+        self.latency_p50_ms = percentile(&self.latencies, 50).map(|d| d.as_millis());
+        self.latency_p95_ms = percentile(&self.latencies, 95).map(|d| d.as_millis());
+        for indication in &indications.values {
+            *self
+                .indications
+                .entry(format!("{:?}", indication.kind).to_lowercase())
+                .or_insert(0) += 1;
+        }
+        self
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec!["| | |".to_string(), "| --- | --- |".to_string()];
+        for (label, value) in self.rows() {
+            lines.push(format!("| {} | {} |", label, value));
+        }
+        lines.join("\n")
+    }
+
+    /// Labelled figures of the summary, for reporters rendering a table.
+    pub fn rows(&self) -> Vec<(String, String)> {
+        let mut rows = vec![
+            (
+                "Hunks".to_string(),
+                format!(
+                    "{} linted, {} skipped, {} from cache",
+                    self.hunks_linted, self.hunks_skipped, self.cache_hits
+                ),
+            ),
+            (
+                "Requests".to_string(),
+                format!("{} ({} retries)", self.requests, self.retries),
+            ),
+            ("Latency".to_string(), self.latency()),
+            ("Indications".to_string(), self.indication_counts()),
+        ];
+        for (model, usage) in &self.models {
+            rows.push((
+                model.clone(),
+                format!(
+                    "{} requests, {} prompt / {} completion tokens, {}",
+                    usage.requests,
+                    usage.prompt_tokens,
+                    usage.completion_tokens,
+                    self.format_cost(usage.cost)
+                ),
+            ));
+        }
+        rows
+    }
+
+    fn latency(&self) -> String {
+        match (self.latency_p50_ms, self.latency_p95_ms) {
+            (Some(p50), Some(p95)) => format!("p50 {} ms, p95 {} ms", p50, p95),
+            _ => "-".to_string(),
+        }
+    }
+
+    fn indication_counts(&self) -> String {
+        let mut counts = self
+            .indications
+            .iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect::<Vec<String>>();
+        if self.outvoted > 0 {
+            counts.push(format!("{} outvoted", self.outvoted));
+        }
+        if counts.is_empty() {
+            return "none".to_string();
+        }
+        counts.join(", ")
+    }
+
+    fn format_cost(&self, cost: Option<f64>) -> String {
+        match cost {
+            Some(cost) => format!("{:.4} {}", cost, self.currency),
+            None => "unknown cost".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "hunks: {} linted, {} skipped, {} from cache",
+            self.hunks_linted, self.hunks_skipped, self.cache_hits
+        )?;
+        writeln!(
+            f,
+            "requests: {} ({} retries), latency: {}",
+            self.requests,
+            self.retries,
+            self.latency()
+        )?;
+        for (model, usage) in &self.models {
+            writeln!(
+                f,
+                "{}: {} prompt / {} completion tokens, {}",
+                model,
+                usage.prompt_tokens,
+                usage.completion_tokens,
+                self.format_cost(usage.cost)
+            )?;
+        }
+        write!(f, "indications: {}", self.indication_counts())
+    }
+}
+
+/// Nearest-rank percentile of sorted values.
+fn percentile(sorted: &[Duration], p: usize) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    Some(sorted[rank - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let latencies = (1..=20).map(Duration::from_secs).collect::<Vec<Duration>>();
+
+        assert_eq!(percentile(&[], 50), None);
+        assert_eq!(percentile(&latencies, 50), Some(Duration::from_secs(10)));
+        assert_eq!(percentile(&latencies, 95), Some(Duration::from_secs(19)));
+    }
+
+    #[test]
+    fn test_record_request() {
+        let mut summary = Summary::default();
+        let usage = Usage {
+            prompt_tokens: 100,
+            completion_tokens: 10,
+        };
+        summary.record_request("gpt-4o", &usage, Some(1.0), 1, Duration::from_secs(1));
+        summary.record_request("gpt-4o", &usage, Some(2.0), 0, Duration::from_secs(2));
+        summary.record_request("local", &usage, None, 0, Duration::from_secs(3));
+
+        assert_eq!(summary.requests, 3);
+        assert_eq!(summary.retries, 1);
+        assert_eq!(summary.models["gpt-4o"].prompt_tokens, 200);
+        assert_eq!(summary.cost(), 3.0);
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::core::{Indication, LineKind, Patch, Patches};
+
+/// Rules disabled on lines of the hunks by comments in the code:
+///
+/// - `llm-lint-disable-next-line RULES reason` on the line before,
+/// - `llm-lint-disable RULES reason` up to `llm-lint-enable RULES`, or the end of the file.
+///
+/// `RULES` are names of rules separated by commas, or `*` or nothing for all rules.
+/// Directives are found in any kind of comment, since only the text is looked at.
+#[derive(Default)]
+pub struct Suppressions {
+    /// Disabled rules by path and by the line numbers indications refer to.
+    lines: HashMap<String, HashMap<u64, Vec<Scope>>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Scope {
+    All,
+    Rule(String),
+}
+
+impl Scope {
+    fn covers(&self, rule: Option<&str>) -> bool {
+        match self {
+            Scope::All => true,
+            Scope::Rule(name) => rule == Some(name.as_str()),
+        }
+    }
+}
+
+enum Directive {
+    DisableNextLine(Vec<Scope>),
+    Disable(Vec<Scope>),
+    Enable(Vec<Scope>),
+}
+
+impl Suppressions {
+    /// Reads the directives in the hunks, and in the whole files of the working tree so
+    /// that blocks disabled above a hunk are known too.
+    pub fn read(patches: &Patches) -> Suppressions {
+        Suppressions::read_with(patches, |path| read_to_string(path).ok())
+    }
+
+    fn read_with(patches: &Patches, read_file: impl Fn(&str) -> Option<String>) -> Suppressions {
+        let mut suppressions = Suppressions::default();
+        let mut files = HashMap::new();
+        for patch in &patches.all {
+            let file = files
+                .entry(patch.path.clone())
+                .or_insert_with(|| read_file(&patch.path))
+                .as_deref();
+            let lines = new_lines(patch);
+            // The working tree may be at another commit than the diff, e.g. a merge commit.
+            let scopes = match file.filter(|file| matches(file, &lines)) {
+                Some(file) => {
+                    let scopes = scan(file.lines());
+                    lines
+                        .iter()
+                        .map(|(_, new_line, _)| scopes[*new_line as usize - 1].clone())
+                        .collect()
+                }
+                None => scan(lines.iter().map(|(_, _, text)| text.as_str())),
+            };
+            let by_line = suppressions.lines.entry(patch.path.clone()).or_default();
+            for ((number, _, _), scopes) in lines.iter().zip(scopes) {
+                by_line.entry(*number).or_default().extend(scopes);
+            }
+        }
+        suppressions
+    }
+
+    /// Whether `rule` is disabled on any line of the location of the indication.
+    pub fn suppresses(&self, indication: &Indication) -> bool {
+        let location = &indication.location;
+        let Some(by_line) = self.lines.get(&location.path) else {
+            return false;
+        };
+        (location.start_line..=location.end_line).any(|number| {
+            by_line.get(&number).is_some_and(|scopes| {
+                scopes
+                    .iter()
+                    .any(|scope| scope.covers(indication.rule.as_deref()))
+            })
+        })
+    }
+
+    /// Whether `rule` is disabled on every line of the hunk, so that it need not be linted.
+    pub fn disables(&self, patch: &Patch, rule: &str) -> bool {
+        let Some(by_line) = self.lines.get(&patch.path) else {
+            return false;
+        };
+        let mut lines = new_lines(patch).into_iter().peekable();
+        lines.peek().is_some()
+            && lines.all(|(number, _, _)| {
+                by_line
+                    .get(&number)
+                    .is_some_and(|scopes| scopes.iter().any(|scope| scope.covers(Some(rule))))
+            })
+    }
+}
+
+/// Lines of the hunk in the new file: the numbers indications refer to, the numbers in
+/// the new file and the text.
+fn new_lines(patch: &Patch) -> Vec<(u64, u64, String)> {
+    let mut new_line = patch.start_line;
+    let mut lines = Vec::new();
+    for (number, kind, text) in patch.diff_lines() {
+        if kind == LineKind::Removed {
+            continue;
+        }
+        lines.push((number, new_line, text));
+        new_line += 1;
+    }
+    lines
+}
+
+fn matches(file: &str, lines: &[(u64, u64, String)]) -> bool {
+    let file = file.lines().collect::<Vec<&str>>();
+    lines.iter().all(|(_, new_line, text)| {
+        *new_line >= 1 && file.get(*new_line as usize - 1) == Some(&text.as_str())
+    })
+}
+
+/// Disabled rules on each of the consecutive lines.
+fn scan<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<Vec<Scope>> {
+    let mut disabled: Vec<Scope> = Vec::new();
+    let mut next_line: Vec<Scope> = Vec::new();
+    let mut scopes = Vec::new();
+    for line in lines {
+        let mut line_scopes = disabled.clone();
+        line_scopes.append(&mut next_line);
+        scopes.push(line_scopes);
+        match directive(line) {
+            Some(Directive::DisableNextLine(rules)) => next_line = rules,
+            Some(Directive::Disable(rules)) => disabled.extend(rules),
+            Some(Directive::Enable(rules)) => {
+                if rules.contains(&Scope::All) {
+                    disabled.clear();
+                } else {
+                    disabled.retain(|scope| !rules.contains(scope));
+                }
+            }
+            None => {}
+        }
+    }
+    scopes
+}
+
+/// Compiled once, since every line of the hunks and files is matched.
+static DIRECTIVE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"llm-lint-(disable-next-line|disable|enable)\b(.*)").unwrap());
+
+fn directive(line: &str) -> Option<Directive> {
+    let c = DIRECTIVE.captures(line)?;
+    // Only the first word names rules, the rest is the reason.
+    let rules = c[2]
+        .trim()
+        .trim_end_matches("-->")
+        .trim_end_matches("*/")
+        .split_whitespace()
+        .next()
+        .filter(|rules| *rules != "*")
+        .map_or(vec![Scope::All], |rules| {
+            rules
+                .split(',')
+                .filter(|rule| !rule.is_empty())
+                .map(|rule| Scope::Rule(rule.to_string()))
+                .collect()
+        });
+    Some(match &c[1] {
+        "disable-next-line" => Directive::DisableNextLine(rules),
+        "disable" => Directive::Disable(rules),
+        _ => Directive::Enable(rules),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn test_suppresses() {
+        let patches = Patches::parse(indoc! {"
+            --- a/src/main.rs
+            +++ b/src/main.rs
+            @@ -3,4 +3,6 @@
+                 let a = x.unwrap();
+            -    let b = y.unwrap();
+            +    // llm-lint-disable-next-line for-rust it is checked above
+            +    let b = y.unwrap();
+                 let c = z.unwrap();
+            +    let d = w.unwrap();
+             }
+        "})
+        .unwrap();
+        let file = indoc! {"
+            fn main() {
+                // llm-lint-disable naming
+                let a = x.unwrap();
+                // llm-lint-disable-next-line for-rust it is checked above
+                let b = y.unwrap();
+                let c = z.unwrap();
+                let d = w.unwrap();
+            }
+        "};
+        let indication = |line, rule: &str| Indication {
+            rule: Some(rule.to_string()),
+            ..Indication::for_test("src/main.rs", line, line)
+        };
+
+        // Lines 3 to 8 as shown to the model: a, removed b, comment, b, c, d.
+        let from_hunks = Suppressions::read_with(&patches, |_| None);
+        assert!(from_hunks.suppresses(&indication(6, "for-rust")));
+        assert!(!from_hunks.suppresses(&indication(6, "naming")));
+        assert!(!from_hunks.suppresses(&indication(7, "for-rust")));
+        assert!(!from_hunks.suppresses(&indication(3, "naming")));
+
+        let from_file = Suppressions::read_with(&patches, |_| Some(file.to_string()));
+        assert!(from_file.suppresses(&indication(6, "for-rust")));
+        assert!(from_file.suppresses(&indication(3, "naming")));
+        assert!(from_file.suppresses(&indication(8, "naming")));
+        assert!(from_file.disables(&patches.all[0], "naming"));
+        assert!(!from_file.disables(&patches.all[0], "for-rust"));
+
+        // Context lines looking like removed ones, e.g. Markdown list items, are kept.
+        let patches = Patches::parse(indoc! {"
+            --- a/README.md
+            +++ b/README.md
+            @@ -1,3 +1,4 @@
+             - first
+            +- second
+             <!-- llm-lint-disable naming -->
+             - third
+        "})
+        .unwrap();
+        let file = "- first\n- second\n<!-- llm-lint-disable naming -->\n- third\n";
+        let markdown = Suppressions::read_with(&patches, |_| Some(file.to_string()));
+        let in_readme = |line| Indication {
+            rule: Some("naming".to_string()),
+            ..Indication::for_test("README.md", line, line)
+        };
+        assert!(markdown.suppresses(&in_readme(4)));
+        assert!(!markdown.suppresses(&in_readme(2)));
+
+        // A file at another commit is ignored.
+        let stale = Suppressions::read_with(&patches, |_| Some("// llm-lint-disable".to_string()));
+        assert!(!stale.suppresses(&indication(3, "naming")));
+    }
+}
@@ -0,0 +1,34 @@
+/// Estimates the number of tokens of the text for the model without a tokenizer.
+///
+/// ASCII is counted by the average bytes per token of the model's encoding, and other
+/// characters (e.g. Japanese) as one token each, which slightly overestimates.
+pub fn estimate(model: &str, text: &str) -> usize {
+    let ascii = text.bytes().filter(|b| b.is_ascii()).count();
+    let others = text.chars().filter(|c| !c.is_ascii()).count();
+    (ascii as f64 / ascii_bytes_per_token(model)).ceil() as usize + others
+}
+
+fn ascii_bytes_per_token(model: &str) -> f64 {
+    if model.starts_with("gpt-4o") || model.starts_with("o1") {
+        // o200k_base
+        4.2
+    } else if model.starts_with("gpt-4") || model.starts_with("gpt-3.5") {
+        // cl100k_base
+        3.8
+    } else {
+        3.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        assert_eq!(estimate("gpt-4o", ""), 0);
+        assert_eq!(estimate("gpt-4o", "fn main() {}"), 3);
+        assert_eq!(estimate("gpt-4o", "ルール"), 3);
+        assert!(estimate("unknown", "fn main() {}") > estimate("gpt-4o", "fn main() {}"));
+    }
+}